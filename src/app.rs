@@ -0,0 +1,52 @@
+use bevy::{app::App, render::RenderApp};
+
+use crate::{SetupPassNode, SetupPassRegistry, SetupResourceNode, SetupResources};
+
+///供其他插件在 build 阶段向 FrameGraph 注册节点
+pub trait FrameGraphAppExt {
+    ///注册到所有视图，相机上的 FrameGraphPasses 可以只选取其中一部分
+    fn add_frame_graph_pass<T: SetupPassNode + Default>(&mut self) -> &mut Self;
+
+    fn insert_frame_graph_pass<T: SetupPassNode>(&mut self, value: T) -> &mut Self;
+
+    fn add_frame_graph_resource<T: SetupResourceNode + Default>(&mut self) -> &mut Self;
+
+    fn insert_frame_graph_resource<T: SetupResourceNode>(&mut self, value: T) -> &mut Self;
+}
+
+impl FrameGraphAppExt for App {
+    fn add_frame_graph_pass<T: SetupPassNode + Default>(&mut self) -> &mut Self {
+        self.insert_frame_graph_pass(T::default())
+    }
+
+    fn insert_frame_graph_pass<T: SetupPassNode>(&mut self, value: T) -> &mut Self {
+        //没有渲染子应用时不需要注册
+        let Some(render_app) = self.get_sub_app_mut(RenderApp) else {
+            return self;
+        };
+
+        render_app
+            .world_mut()
+            .get_resource_or_init::<SetupPassRegistry>()
+            .add_node(value);
+
+        self
+    }
+
+    fn add_frame_graph_resource<T: SetupResourceNode + Default>(&mut self) -> &mut Self {
+        self.insert_frame_graph_resource(T::default())
+    }
+
+    fn insert_frame_graph_resource<T: SetupResourceNode>(&mut self, value: T) -> &mut Self {
+        let Some(render_app) = self.get_sub_app_mut(RenderApp) else {
+            return self;
+        };
+
+        render_app
+            .world_mut()
+            .get_resource_or_init::<SetupResources>()
+            .add_node(value);
+
+        self
+    }
+}
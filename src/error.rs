@@ -1,2 +1,87 @@
-#[derive(Debug)]
-pub enum RendererError {}
+use std::fmt::{self, Display};
+
+use bevy::render::render_resource::{CachedComputePipelineId, CachedRenderPipelineId};
+
+use crate::GraphDiagnostic;
+
+#[derive(Debug, Clone)]
+pub enum RendererError {
+    ///渲染节点的读写关系形成了环
+    PassCycle { pass_names: Vec<String> },
+    ///图的校验没有通过
+    InvalidGraph { diagnostics: Vec<GraphDiagnostic> },
+    ///渲染节点使用的资源不在资源表中
+    MissingResource { resource_index: usize },
+    ///渲染管线还没有编译完成
+    RenderPipelineNotReady(CachedRenderPipelineId),
+    ///计算管线还没有编译完成
+    ComputePipelineNotReady(CachedComputePipelineId),
+    ///后端返回的错误
+    Backend { message: String },
+    ///执行失败的渲染节点名称及其错误
+    PassesFailed {
+        failures: Vec<(String, RendererError)>,
+    },
+    ///setup 节点依赖了没有注册的节点
+    MissingSetupDependency { node: String, dependency: String },
+    ///setup 节点的依赖形成了环
+    SetupNodeCycle { node_names: Vec<String> },
+}
+
+impl RendererError {
+    pub fn backend(message: impl Into<String>) -> Self {
+        RendererError::Backend {
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for RendererError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RendererError::PassCycle { pass_names } => {
+                write!(f, "passes form a cycle: {}", pass_names.join(", "))
+            }
+            RendererError::InvalidGraph { diagnostics } => {
+                write!(f, "frame graph is invalid:")?;
+
+                for diagnostic in diagnostics.iter() {
+                    write!(f, "\n  {}", diagnostic)?;
+                }
+
+                Ok(())
+            }
+            RendererError::MissingResource { resource_index } => {
+                write!(f, "resource {} is not available", resource_index)
+            }
+            RendererError::RenderPipelineNotReady(id) => {
+                write!(f, "render pipeline {} is not ready", id.id())
+            }
+            RendererError::ComputePipelineNotReady(id) => {
+                write!(f, "compute pipeline {} is not ready", id.id())
+            }
+            RendererError::Backend { message } => write!(f, "backend error: {}", message),
+            RendererError::PassesFailed { failures } => {
+                write!(f, "{} pass(es) failed:", failures.len())?;
+
+                for (pass_name, error) in failures.iter() {
+                    write!(f, "\n  {}: {}", pass_name, error)?;
+                }
+
+                Ok(())
+            }
+            RendererError::MissingSetupDependency { node, dependency } => {
+                write!(
+                    f,
+                    "setup node `{}` depends on unregistered node `{}`",
+                    node, dependency
+                )
+            }
+            RendererError::SetupNodeCycle { node_names } => {
+                write!(f, "setup nodes form a cycle: {}", node_names.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for RendererError {}
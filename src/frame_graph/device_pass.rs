@@ -1,16 +1,21 @@
-use crate::{Device, RenderPassInfo, TypeHandle};
+use crate::{
+    ColorAttachment, DepthStencilAttachment, DepthStencilTexture, Device, LoadOp, RenderPassInfo,
+    RendererError, TypeHandle,
+};
 
 use super::{
-    DynPass, FrameGraph, PassNode, RenderContext, ResourceTable, TransientResourceCache,
+    DynPass, FrameGraph, PassKind, PassNode, RenderContext, ResourceTable, TransientResourceCache,
     VirtualResource,
 };
 
 pub struct DevicePass {
     logic_passes: Vec<LogicPass>,
     render_pass_info: RenderPassInfo,
+    kind: PassKind,
 }
 
 pub struct LogicPass {
+    name: String,
     pass: DynPass,
     resource_release_array: Vec<TypeHandle<VirtualResource>>,
     resource_request_array: Vec<VirtualResource>,
@@ -50,6 +55,94 @@ impl DevicePass {
         Self {
             logic_passes: vec![],
             render_pass_info: RenderPassInfo::default(),
+            kind: PassKind::default(),
+        }
+    }
+
+    ///渲染节点能否并入当前的DevicePass。
+    ///要求附件完全相同，新节点只加载不清除，并且除了加载的上一版本外没有读取这些附件
+    pub fn can_merge(&self, fg: &FrameGraph, handle: TypeHandle<PassNode>) -> bool {
+        let pass_node = fg.get_pass_node(&handle);
+
+        if self.logic_passes.is_empty()
+            || self.kind != PassKind::Render
+            || pass_node.kind != PassKind::Render
+        {
+            return false;
+        }
+
+        let color_attachments = &self.render_pass_info.color_attachments;
+
+        //没有附件的节点不存在可以共用的渲染通道
+        if color_attachments.is_empty() && self.render_pass_info.depth_stencil_attachment.is_none()
+        {
+            return false;
+        }
+
+        if color_attachments.len() != pass_node.color_attachments.len()
+            || !color_attachments
+                .iter()
+                .zip(pass_node.color_attachments.iter())
+                .all(|(a, b)| a.is_same_target(b) && matches!(b.ops.load, LoadOp::Load))
+        {
+            return false;
+        }
+
+        match (
+            &self.render_pass_info.depth_stencil_attachment,
+            &pass_node.depth_stencil_attachment,
+        ) {
+            (None, None) => {}
+            (Some(a), Some(b)) => {
+                let is_load = b
+                    .depth_ops
+                    .is_none_or(|ops| matches!(ops.load, LoadOp::Load))
+                    && b.stencil_ops
+                        .is_none_or(|ops| matches!(ops.load, LoadOp::Load));
+
+                if !a.is_same_target(b) || !is_load {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+
+        let mut attachment_handles = vec![];
+
+        for color_attachment in color_attachments.iter() {
+            attachment_handles.push(color_attachment.view.resource_handle());
+
+            if let Some(resolve_target) = color_attachment.resolve_target.as_ref() {
+                attachment_handles.push(resolve_target.resource_handle());
+            }
+        }
+
+        if let Some(DepthStencilAttachment {
+            texture: DepthStencilTexture::Write(handle),
+            ..
+        }) = self.render_pass_info.depth_stencil_attachment.as_ref()
+        {
+            attachment_handles.push(handle.resource_handle());
+        }
+
+        let load_reads = pass_node.load_reads(fg);
+
+        !pass_node
+            .reads
+            .iter()
+            .filter(|resource_node_handle| !load_reads.contains(resource_node_handle))
+            .any(|resource_node_handle| {
+                let resource_handle = fg.get_resource_node(resource_node_handle).resource_handle();
+                attachment_handles.contains(&resource_handle)
+            })
+    }
+
+    ///资源描述在别名分配后可能改变，需要重新同步
+    pub fn refresh_resource_requests(&mut self, fg: &FrameGraph) {
+        for logic_pass in self.logic_passes.iter_mut() {
+            for resource in logic_pass.resource_request_array.iter_mut() {
+                *resource = fg.get_resource(&resource.info.handle).clone();
+            }
         }
     }
 
@@ -62,26 +155,55 @@ impl DevicePass {
             .collect();
 
         let pass_node = fg.get_pass_node_mut(&handle);
+        self.kind = pass_node.kind;
 
         let logic_pass = LogicPass {
+            name: pass_node.name.clone(),
             pass: pass_node.pass.take().unwrap(),
             resource_release_array: pass_node.resource_release_array.clone(),
             resource_request_array,
         };
 
-        self.render_pass_info
-            .color_attachments
-            .append(&mut pass_node.color_attachments);
+        if self.logic_passes.is_empty() {
+            self.render_pass_info
+                .color_attachments
+                .append(&mut pass_node.color_attachments);
+
+            if let Some(depth_stencil_attachment) = pass_node.depth_stencil_attachment.take() {
+                self.render_pass_info.depth_stencil_attachment = Some(depth_stencil_attachment);
+            }
+        } else {
+            //合并时附件相同，只需沿用最后一个节点的存储方式
+            merge_store_ops(
+                &mut self.render_pass_info,
+                &pass_node.color_attachments,
+                pass_node.depth_stencil_attachment.as_ref(),
+            );
+        }
 
         self.logic_passes.push(logic_pass);
     }
 
     pub fn execute(&mut self, render_context: &mut RenderContext) {
-        self.begin(render_context);
+        if let Err(e) = self.begin(render_context) {
+            //通道没有开启，其中的渲染节点都无法执行
+            for logic_pass in self.logic_passes.iter() {
+                render_context
+                    .failures
+                    .push((logic_pass.name.clone(), e.clone()));
+
+                logic_pass.release_resources(
+                    &mut render_context.resource_table,
+                    render_context.transient_resource_cache,
+                );
+            }
+
+            return;
+        }
 
         for logic_pass in self.logic_passes.iter_mut() {
             if let Err(e) = logic_pass.pass.execute(render_context) {
-                println!("{:?}", e);
+                render_context.failures.push((logic_pass.name.clone(), e));
             }
 
             logic_pass.release_resources(
@@ -93,7 +215,7 @@ impl DevicePass {
         self.end(render_context);
     }
 
-    pub fn begin(&mut self, render_context: &mut RenderContext) {
+    pub fn begin(&mut self, render_context: &mut RenderContext) -> Result<(), RendererError> {
         for logic_pass in self.logic_passes.iter() {
             logic_pass.request_resources(
                 render_context.device,
@@ -102,24 +224,56 @@ impl DevicePass {
             );
         }
 
-        let mut command_buffer = render_context.device().create_command_buffer();
-
-        let mut render_pass = render_context
-            .device()
-            .create_render_pass(&self.render_pass_info);
-        render_pass.do_init(render_context);
-        command_buffer.begin_render_pass(render_context.device(), render_pass);
+        let device = render_context.device.clone();
 
-        render_context.set_cb(command_buffer);
+        match self.kind {
+            PassKind::Render => {
+                let mut render_pass = device.create_render_pass(&self.render_pass_info);
+                render_pass.do_init(render_context);
+                render_context
+                    .get_or_create_cb()
+                    .begin_render_pass(&device, render_pass)
+            }
+            PassKind::Compute => render_context
+                .get_or_create_cb()
+                .begin_compute_pass(&device),
+        }
     }
 
+    ///资源表在整个 FrameGraph 执行期间保留，资源只在最后使用的节点之后归还缓存
     pub fn end(&self, render_context: &mut RenderContext) {
-        render_context.resource_table = ResourceTable::default();
+        if let Some(command_buffer) = render_context.cb.as_mut() {
+            match self.kind {
+                PassKind::Render => command_buffer.end_render_pass(),
+                PassKind::Compute => command_buffer.end_compute_pass(),
+            }
+        }
+    }
+}
+
+fn merge_store_ops(
+    render_pass_info: &mut RenderPassInfo,
+    color_attachments: &[ColorAttachment],
+    depth_stencil_attachment: Option<&DepthStencilAttachment>,
+) {
+    for (a, b) in render_pass_info
+        .color_attachments
+        .iter_mut()
+        .zip(color_attachments.iter())
+    {
+        a.ops.store = b.ops.store;
+    }
 
-        if let Some(mut command_buffer) = render_context.take_cb() {
-            command_buffer.end_render_pass();
+    if let (Some(a), Some(b)) = (
+        render_pass_info.depth_stencil_attachment.as_mut(),
+        depth_stencil_attachment,
+    ) {
+        if let (Some(a_ops), Some(b_ops)) = (a.depth_ops.as_mut(), b.depth_ops) {
+            a_ops.store = b_ops.store;
+        }
 
-            render_context.queue_cbs.push(command_buffer);
+        if let (Some(a_ops), Some(b_ops)) = (a.stencil_ops.as_mut(), b.stencil_ops) {
+            a_ops.store = b_ops.store;
         }
     }
 }
@@ -0,0 +1,82 @@
+use std::fmt::{self, Display};
+
+///FrameGraph::validate 发现的问题
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphDiagnostic {
+    ///渲染节点没有调用 set_pass
+    MissingPassData { pass_name: String },
+    ///读取了从未被写入的临时资源
+    ReadBeforeWrite {
+        pass_name: String,
+        resource_name: String,
+    },
+    ///写入了只读的导入资源
+    WriteToReadOnlyImport {
+        pass_name: String,
+        resource_name: String,
+    },
+    ///句柄不属于当前图，通常是上一帧遗留的句柄
+    InvalidHandle { pass_name: String },
+    ///keep 传入的句柄不属于当前图
+    InvalidKeptHandle { resource_node_index: usize },
+    ///附件引用的资源类型与附件要求的不一致
+    ResourceTypeMismatch {
+        pass_name: String,
+        resource_name: String,
+        expected: &'static str,
+    },
+    ///多个资源使用了相同的名称
+    DuplicateResourceName { resource_name: String },
+}
+
+impl Display for GraphDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphDiagnostic::MissingPassData { pass_name } => {
+                write!(f, "pass `{}` has no pass data", pass_name)
+            }
+            GraphDiagnostic::ReadBeforeWrite {
+                pass_name,
+                resource_name,
+            } => write!(
+                f,
+                "pass `{}` reads `{}` before anything writes it",
+                pass_name, resource_name
+            ),
+            GraphDiagnostic::WriteToReadOnlyImport {
+                pass_name,
+                resource_name,
+            } => write!(
+                f,
+                "pass `{}` writes read-only import `{}`",
+                pass_name, resource_name
+            ),
+            GraphDiagnostic::InvalidHandle { pass_name } => {
+                write!(f, "pass `{}` uses a handle from another graph", pass_name)
+            }
+            GraphDiagnostic::InvalidKeptHandle {
+                resource_node_index,
+            } => write!(
+                f,
+                "kept resource node {} belongs to another graph",
+                resource_node_index
+            ),
+            GraphDiagnostic::ResourceTypeMismatch {
+                pass_name,
+                resource_name,
+                expected,
+            } => write!(
+                f,
+                "pass `{}` expects `{}` to be a {}",
+                pass_name, resource_name, expected
+            ),
+            GraphDiagnostic::DuplicateResourceName { resource_name } => {
+                write!(
+                    f,
+                    "resource name `{}` is used more than once",
+                    resource_name
+                )
+            }
+        }
+    }
+}
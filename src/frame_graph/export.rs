@@ -0,0 +1,113 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+
+use super::PassKind;
+
+///FrameGraph 的快照，用于调试时查看图的结构
+#[derive(Debug, Serialize)]
+pub struct FrameGraphExport {
+    ///是否已经编译，未编译时剔除状态和生命周期都没有意义
+    pub compiled: bool,
+    pub pass_nodes: Vec<PassNodeExport>,
+    pub resources: Vec<ResourceExport>,
+    pub resource_nodes: Vec<ResourceNodeExport>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PassNodeExport {
+    pub index: usize,
+    pub name: String,
+    pub insert_point: usize,
+    pub kind: PassKind,
+    pub culled: bool,
+    ///管线没有准备好而跳过
+    pub skipped: bool,
+    pub has_side_effect: bool,
+    ///读取的资源节点索引
+    pub reads: Vec<usize>,
+    ///写入的资源节点索引
+    pub writes: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResourceExport {
+    pub index: usize,
+    pub name: String,
+    pub imported: bool,
+    pub kept: bool,
+    pub first_pass_node: Option<usize>,
+    pub last_pass_node: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResourceNodeExport {
+    pub index: usize,
+    pub resource: usize,
+    pub version: u32,
+    pub writer: Option<usize>,
+}
+
+impl FrameGraphExport {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::new();
+
+        writeln!(dot, "digraph FrameGraph {{").unwrap();
+        writeln!(dot, "    rankdir=LR;").unwrap();
+
+        for pass_node in self.pass_nodes.iter() {
+            let style = if pass_node.culled {
+                "dashed"
+            } else if pass_node.skipped {
+                "dotted"
+            } else {
+                "solid"
+            };
+
+            writeln!(
+                dot,
+                "    pass_{} [shape=box, style={}, label={:?}];",
+                pass_node.index,
+                style,
+                format!("{} ({:?})", pass_node.name, pass_node.kind)
+            )
+            .unwrap();
+        }
+
+        for resource_node in self.resource_nodes.iter() {
+            let resource = &self.resources[resource_node.resource];
+            let style = if resource.imported || resource.kept {
+                "bold"
+            } else {
+                "solid"
+            };
+
+            writeln!(
+                dot,
+                "    resource_{} [shape=ellipse, style={}, label={:?}];",
+                resource_node.index,
+                style,
+                format!("{} v{}", resource.name, resource_node.version)
+            )
+            .unwrap();
+        }
+
+        for pass_node in self.pass_nodes.iter() {
+            for read in pass_node.reads.iter() {
+                writeln!(dot, "    resource_{} -> pass_{};", read, pass_node.index).unwrap();
+            }
+
+            for write in pass_node.writes.iter() {
+                writeln!(dot, "    pass_{} -> resource_{};", pass_node.index, write).unwrap();
+            }
+        }
+
+        writeln!(dot, "}}").unwrap();
+
+        dot
+    }
+}
@@ -1,14 +1,21 @@
-use std::sync::Arc;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+};
 
 use bevy::{
     ecs::component::Component,
-    render::render_resource::{CachedRenderPipelineId, PipelineCache},
+    render::render_resource::{CachedComputePipelineId, CachedRenderPipelineId},
 };
 
-use crate::Device;
+use crate::{ColorAttachmentView, Device, RendererError};
 
 use super::{
-    DevicePass, ImportToFrameGraph, RenderContext, ResourceBoard,
+    AnyFGResourceDescriptor, DevicePass, FrameGraphExport, GraphDiagnostic, ImportToFrameGraph,
+    PassNodeExport, PipelineProvider, RenderContext, ResourceBoard, ResourceExport,
+    ResourceNodeExport, ResourceState, TransientAllocator, TransientMemoryReport,
     handle::TypeHandle,
     pass_node::PassNode,
     pass_node_builder::PassNodeBuilder,
@@ -17,18 +24,75 @@ use super::{
     transient_resource_cache::TransientResourceCache,
 };
 
-#[derive(Default, Debug)]
+///渲染节点执行前需要编译完成的管线
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct CompiledPipelines {
     pub render_pipeline_ids: Vec<CachedRenderPipelineId>,
+    pub compute_pipeline_ids: Vec<CachedComputePipelineId>,
+}
+
+impl CompiledPipelines {
+    pub fn is_empty(&self) -> bool {
+        self.render_pipeline_ids.is_empty() && self.compute_pipeline_ids.is_empty()
+    }
+
+    ///返回还没有编译完成的管线
+    pub fn pending(&self, pipeline_provider: &dyn PipelineProvider) -> CompiledPipelines {
+        CompiledPipelines {
+            render_pipeline_ids: self
+                .render_pipeline_ids
+                .iter()
+                .filter(|id| pipeline_provider.get_render_pipeline(**id).is_none())
+                .copied()
+                .collect(),
+            compute_pipeline_ids: self
+                .compute_pipeline_ids
+                .iter()
+                .filter(|id| pipeline_provider.get_compute_pipeline(**id).is_none())
+                .copied()
+                .collect(),
+        }
+    }
+}
+
+///因为管线没有准备好，或者依赖的节点被跳过而跳过的渲染节点
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedPass {
+    pub pass_name: String,
+    pub pending_pipelines: CompiledPipelines,
+    ///读取了这些被跳过节点的输出，因此一并跳过
+    pub skipped_dependencies: Vec<String>,
+}
+
+///上一次编译的结果，图的结构不变时直接复用
+struct CompileCache {
+    structure_hash: u64,
+    sorted_pass_nodes: Vec<TypeHandle<PassNode>>,
+    pass_node_ref_counts: Vec<u32>,
+    resource_node_ref_counts: Vec<u32>,
+    ///包含每个资源的生命周期
+    resource_infos: Vec<ResourceInfo>,
+    ///每个DevicePass包含的渲染节点
+    device_pass_groups: Vec<Vec<TypeHandle<PassNode>>>,
 }
 
 #[derive(Default, Component)]
 pub struct FrameGraph {
     pass_nodes: Vec<PassNode>,
+    ///拓扑排序后的渲染节点执行顺序
+    sorted_pass_nodes: Vec<TypeHandle<PassNode>>,
     resources: Vec<VirtualResource>,
     resource_nodes: Vec<ResourceNode>,
     device_passes: Option<Vec<DevicePass>>,
     resource_board: ResourceBoard,
+    skipped_passes: Vec<SkippedPass>,
+    ///keep 时传入的无效句柄，交给 validate 报告
+    invalid_kept_handles: Vec<TypeHandle<ResourceNode>>,
+    ///reset 时保留，供下一帧复用
+    compile_cache: Option<CompileCache>,
+    ///最近一次编译是否复用了缓存
+    compile_cache_hit: bool,
+    transient_memory_report: TransientMemoryReport,
 }
 
 impl FrameGraph {
@@ -37,16 +101,38 @@ impl FrameGraph {
         self.resource_nodes = vec![];
         self.resources = vec![];
         self.pass_nodes = vec![];
+        self.sorted_pass_nodes = vec![];
+        self.skipped_passes = vec![];
+        self.invalid_kept_handles = vec![];
+    }
+
+    ///最近一次编译中跳过的渲染节点
+    pub fn skipped_passes(&self) -> &[SkippedPass] {
+        &self.skipped_passes
+    }
+
+    pub fn is_compile_cache_hit(&self) -> bool {
+        self.compile_cache_hit
+    }
+
+    pub fn clear_compile_cache(&mut self) {
+        self.compile_cache = None;
+    }
+
+    ///最近一次编译中临时资源的显存占用
+    pub fn transient_memory_report(&self) -> TransientMemoryReport {
+        self.transient_memory_report
     }
 
     pub fn execute(
         &mut self,
         device: &Arc<Device>,
         transient_resource_cache: &mut TransientResourceCache,
-        pipeline_cache: &PipelineCache,
-    ) {
+        pipeline_cache: &dyn PipelineProvider,
+    ) -> Result<(), RendererError> {
         if self.device_passes.is_none() {
-            return;
+            self.reset();
+            return Ok(());
         }
 
         let mut render_context =
@@ -58,18 +144,202 @@ impl FrameGraph {
             device_pass.execute(&mut render_context);
         }
 
-        render_context.device.submit(render_context.queue_cbs);
+        render_context.flush();
+        let submitted = render_context.device.submit(render_context.queue_cbs);
 
         self.reset();
+
+        //提交失败时整帧都没有生效
+        submitted?;
+
+        //失败的渲染节点不影响其他节点的提交
+        if render_context.failures.is_empty() {
+            Ok(())
+        } else {
+            Err(RendererError::PassesFailed {
+                failures: render_context.failures,
+            })
+        }
+    }
+
+    ///根据渲染节点的读写关系进行拓扑排序，insert_point 仅用于决定无依赖节点之间的顺序
+    fn sort(&mut self) -> Result<(), RendererError> {
+        let pass_count = self.pass_nodes.len();
+        let mut successors: Vec<Vec<usize>> = vec![vec![]; pass_count];
+        let mut in_degrees = vec![0; pass_count];
+
+        let mut add_edge = |from: TypeHandle<PassNode>, to: TypeHandle<PassNode>| {
+            if from == to || successors[from.index()].contains(&to.index()) {
+                return;
+            }
+
+            successors[from.index()].push(to.index());
+            in_degrees[to.index()] += 1;
+        };
+
+        //每个资源节点的读取者
+        let mut readers: Vec<Vec<TypeHandle<PassNode>>> = vec![vec![]; self.resource_nodes.len()];
+        for pass_node in self.pass_nodes.iter() {
+            for resource_node_handle in pass_node.reads.iter() {
+                readers[resource_node_handle.index()].push(pass_node.handle);
+            }
+        }
+
+        let versions: HashMap<(usize, u32), usize> = self
+            .resource_nodes
+            .iter()
+            .map(|resource_node| {
+                (
+                    (
+                        resource_node.resource_handle().index(),
+                        resource_node.version,
+                    ),
+                    resource_node.resource_node_handle().index(),
+                )
+            })
+            .collect();
+
+        for pass_node in self.pass_nodes.iter() {
+            //先写后读
+            for resource_node_handle in pass_node.reads.iter() {
+                let resource_node = &self.resource_nodes[resource_node_handle.index()];
+                if let Some(writer_handle) = resource_node.writer_handle {
+                    add_edge(writer_handle, pass_node.handle);
+                }
+            }
+
+            //写入新版本前，旧版本的写入者和读取者必须已经执行
+            for resource_node_handle in pass_node.writes.iter() {
+                let resource_node = &self.resource_nodes[resource_node_handle.index()];
+                if resource_node.version == 0 {
+                    continue;
+                }
+
+                let Some(&previous_index) = versions.get(&(
+                    resource_node.resource_handle().index(),
+                    resource_node.version - 1,
+                )) else {
+                    continue;
+                };
+
+                let previous = &self.resource_nodes[previous_index];
+                if let Some(writer_handle) = previous.writer_handle {
+                    add_edge(writer_handle, pass_node.handle);
+                }
+
+                for reader_handle in readers[previous_index].iter() {
+                    add_edge(*reader_handle, pass_node.handle);
+                }
+            }
+        }
+
+        let mut queue = BinaryHeap::new();
+        for (index, in_degree) in in_degrees.iter().enumerate() {
+            if *in_degree == 0 {
+                queue.push(Reverse((self.pass_nodes[index].insert_point, index)));
+            }
+        }
+
+        let mut sorted_pass_nodes = Vec::with_capacity(pass_count);
+
+        while let Some(Reverse((_, index))) = queue.pop() {
+            sorted_pass_nodes.push(self.pass_nodes[index].handle);
+
+            for successor in successors[index].iter() {
+                in_degrees[*successor] -= 1;
+
+                if in_degrees[*successor] == 0 {
+                    queue.push(Reverse((
+                        self.pass_nodes[*successor].insert_point,
+                        *successor,
+                    )));
+                }
+            }
+        }
+
+        if sorted_pass_nodes.len() != pass_count {
+            let pass_names = in_degrees
+                .iter()
+                .enumerate()
+                .filter(|(_, in_degree)| **in_degree > 0)
+                .map(|(index, _)| self.pass_nodes[index].name.clone())
+                .collect();
+
+            return Err(RendererError::PassCycle { pass_names });
+        }
+
+        self.sorted_pass_nodes = sorted_pass_nodes;
+
+        Ok(())
     }
 
-    fn sort(&mut self) {
-        self.pass_nodes
-            .sort_by(|a, b| a.insert_point.cmp(&b.insert_point));
+    fn is_retained(&self, resource_node_handle: TypeHandle<ResourceNode>) -> bool {
+        let resource_node = &self.resource_nodes[resource_node_handle.index()];
+        let resource = &self.resources[resource_node.resource_handle().index()];
+        resource.is_imported() || resource.info.kept
     }
 
-    pub fn compute_resource_lifetime(&mut self) {
+    ///基于引用计数剔除写入的资源最终未被导入资源或保留资源使用的渲染节点
+    fn cull(&mut self) {
+        for resource_node in self.resource_nodes.iter_mut() {
+            resource_node.ref_count = 0;
+        }
+
         for pass_node in self.pass_nodes.iter_mut() {
+            pass_node.ref_count = pass_node.writes.len() as u32;
+
+            for resource_node_handle in pass_node.reads.iter() {
+                self.resource_nodes[resource_node_handle.index()].ref_count += 1;
+            }
+        }
+
+        let mut stack: Vec<TypeHandle<ResourceNode>> = self
+            .resource_nodes
+            .iter()
+            .map(|resource_node| resource_node.resource_node_handle())
+            .filter(|handle| {
+                self.resource_nodes[handle.index()].ref_count == 0 && !self.is_retained(*handle)
+            })
+            .collect();
+
+        while let Some(resource_node_handle) = stack.pop() {
+            let Some(writer_handle) =
+                self.resource_nodes[resource_node_handle.index()].writer_handle
+            else {
+                continue;
+            };
+
+            let writer = &mut self.pass_nodes[writer_handle.index()];
+            writer.ref_count -= 1;
+
+            if !writer.is_culled() {
+                continue;
+            }
+
+            for read_handle in writer.reads.clone() {
+                let resource_node = &mut self.resource_nodes[read_handle.index()];
+                resource_node.ref_count -= 1;
+
+                if resource_node.ref_count == 0 && !self.is_retained(read_handle) {
+                    stack.push(read_handle);
+                }
+            }
+        }
+    }
+
+    pub fn compute_resource_lifetime(&mut self) {
+        self.update_resource_lifetime();
+        self.assign_resource_requests();
+    }
+
+    fn update_resource_lifetime(&mut self) {
+        for pass_node_handle in self.sorted_pass_nodes.iter() {
+            let pass_node = &self.pass_nodes[pass_node_handle.index()];
+
+            if !pass_node.should_execute() {
+                continue;
+            }
+
             //更新渲染节点读取的资源节点所指向资源的生命周期
             for resource_node_handle in pass_node.reads.iter() {
                 let resource_node = &self.resource_nodes[resource_node_handle.index()];
@@ -84,7 +354,9 @@ impl FrameGraph {
                 resource.info.update_lifetime(pass_node.handle);
             }
         }
+    }
 
+    fn assign_resource_requests(&mut self) {
         //更新pass_node中资源使用的索引顺序
         for resource_index in 0..self.resources.len() {
             let resource = &self.resources[resource_index];
@@ -104,33 +376,527 @@ impl FrameGraph {
         }
     }
 
-    fn generate_device_passes(&mut self) {
-        let mut device_passes = vec![];
+    ///返回每个DevicePass包含的渲染节点
+    fn generate_device_passes(&mut self) -> Vec<Vec<TypeHandle<PassNode>>> {
+        let mut device_passes: Vec<DevicePass> = vec![];
+        let mut device_pass_groups: Vec<Vec<TypeHandle<PassNode>>> = vec![];
+
+        for pass_node_handle in self.sorted_pass_nodes.clone() {
+            if !self.pass_nodes[pass_node_handle.index()].should_execute() {
+                continue;
+            }
 
-        for index in 0..self.pass_nodes.len() {
-            let pass_node_handle = self.pass_nodes[index].handle;
+            //与上一个DevicePass兼容的渲染节点直接并入，共用一个渲染通道
+            if let Some(device_pass) = device_passes.last_mut()
+                && device_pass.can_merge(self, pass_node_handle)
+            {
+                device_pass.extra(self, pass_node_handle);
+                device_pass_groups
+                    .last_mut()
+                    .unwrap()
+                    .push(pass_node_handle);
+                continue;
+            }
 
             let mut device_pass = DevicePass::new();
 
             device_pass.extra(self, pass_node_handle);
 
             device_passes.push(device_pass);
+            device_pass_groups.push(vec![pass_node_handle]);
         }
 
         self.device_passes = Some(device_passes);
+
+        device_pass_groups
+    }
+
+    fn build_device_passes(&mut self, device_pass_groups: &[Vec<TypeHandle<PassNode>>]) {
+        let mut device_passes = vec![];
+
+        for group in device_pass_groups.iter() {
+            let mut device_pass = DevicePass::new();
+
+            for pass_node_handle in group.iter() {
+                device_pass.extra(self, *pass_node_handle);
+            }
+
+            device_passes.push(device_pass);
+        }
+
+        self.device_passes = Some(device_passes);
+    }
+
+    ///生命周期不重叠且描述兼容的临时资源共用同一份显存
+    fn alias_transient_resources(&mut self, device_pass_groups: &[Vec<TypeHandle<PassNode>>]) {
+        let mut device_pass_indices = vec![0; self.pass_nodes.len()];
+        for (index, group) in device_pass_groups.iter().enumerate() {
+            for pass_node_handle in group.iter() {
+                device_pass_indices[pass_node_handle.index()] = index;
+            }
+        }
+
+        let mut transients = vec![];
+        for resource in self.resources.iter() {
+            if resource.is_imported() {
+                continue;
+            }
+
+            if let (Some(first), Some(last)) = (
+                resource.info.first_pass_node_handle,
+                resource.info.last_pass_node_handle,
+            ) {
+                transients.push((
+                    resource.info.handle,
+                    device_pass_indices[first.index()],
+                    device_pass_indices[last.index()],
+                ));
+            }
+        }
+
+        transients.sort_by_key(|(handle, first, _)| (*first, handle.index()));
+
+        let mut allocator = TransientAllocator::default();
+        let slots: Vec<(TypeHandle<VirtualResource>, usize)> = transients
+            .iter()
+            .map(|(handle, first, last)| {
+                let slot = allocator.allocate(self.resources[handle.index()].desc(), *first, *last);
+                (*handle, slot)
+            })
+            .collect();
+
+        for (handle, slot) in slots {
+            self.resources[handle.index()].state =
+                ResourceState::Setup(allocator.slot_desc(slot).clone());
+        }
+
+        self.transient_memory_report = allocator.report();
+
+        if let Some(mut device_passes) = self.device_passes.take() {
+            for device_pass in device_passes.iter_mut() {
+                device_pass.refresh_resource_requests(self);
+            }
+
+            self.device_passes = Some(device_passes);
+        }
+    }
+
+    ///只包含影响编译结果的部分：节点、资源描述、读写关系、附件以及管线是否就绪
+    fn structure_hash(&self, pending_pipelines: &[CompiledPipelines]) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        for resource in self.resources.iter() {
+            resource.info.name.hash(&mut hasher);
+            resource.desc().hash(&mut hasher);
+            resource.is_imported().hash(&mut hasher);
+            resource.is_read_only().hash(&mut hasher);
+            resource.info.kept.hash(&mut hasher);
+        }
+
+        for resource_node in self.resource_nodes.iter() {
+            resource_node.resource_handle().index().hash(&mut hasher);
+            resource_node.version.hash(&mut hasher);
+        }
+
+        for (pass_node, pending_pipelines) in self.pass_nodes.iter().zip(pending_pipelines.iter()) {
+            pass_node.name.hash(&mut hasher);
+            pass_node.insert_point.hash(&mut hasher);
+            pass_node.kind.hash(&mut hasher);
+            pass_node.has_side_effect.hash(&mut hasher);
+            pass_node.pass.is_some().hash(&mut hasher);
+            pending_pipelines.is_empty().hash(&mut hasher);
+
+            //带上长度，避免不同的读写划分得到相同的哈希
+            pass_node.reads.len().hash(&mut hasher);
+            for handle in pass_node.reads.iter() {
+                handle.index().hash(&mut hasher);
+            }
+
+            pass_node.writes.len().hash(&mut hasher);
+            for handle in pass_node.writes.iter() {
+                handle.index().hash(&mut hasher);
+            }
+
+            pass_node.color_attachments.len().hash(&mut hasher);
+            for color_attachment in pass_node.color_attachments.iter() {
+                color_attachment
+                    .view
+                    .resource_handle()
+                    .index()
+                    .hash(&mut hasher);
+                if let ColorAttachmentView::Texture(_, view_info) = &color_attachment.view {
+                    view_info.hash(&mut hasher);
+                }
+
+                color_attachment
+                    .resolve_target
+                    .as_ref()
+                    .map(|resolve_target| resolve_target.resource_handle().index())
+                    .hash(&mut hasher);
+                std::mem::discriminant(&color_attachment.ops.load).hash(&mut hasher);
+            }
+
+            pass_node
+                .depth_stencil_attachment
+                .is_some()
+                .hash(&mut hasher);
+            if let Some(depth_stencil_attachment) = pass_node.depth_stencil_attachment.as_ref() {
+                std::mem::discriminant(&depth_stencil_attachment.texture).hash(&mut hasher);
+                depth_stencil_attachment
+                    .texture
+                    .resource_handle()
+                    .index()
+                    .hash(&mut hasher);
+                depth_stencil_attachment
+                    .depth_ops
+                    .map(|ops| std::mem::discriminant(&ops.load))
+                    .hash(&mut hasher);
+                depth_stencil_attachment
+                    .stencil_ops
+                    .map(|ops| std::mem::discriminant(&ops.load))
+                    .hash(&mut hasher);
+            }
+        }
+
+        hasher.finish()
     }
 
-    pub fn compile(&mut self) {
+    ///导出图的结构，编译前后都可以调用
+    pub fn export(&self) -> FrameGraphExport {
+        let compiled = self.device_passes.is_some();
+
+        let pass_nodes = self
+            .pass_nodes
+            .iter()
+            .map(|pass_node| PassNodeExport {
+                index: pass_node.handle.index(),
+                name: pass_node.name.clone(),
+                insert_point: pass_node.insert_point,
+                kind: pass_node.kind,
+                culled: compiled && pass_node.is_culled(),
+                skipped: pass_node.skipped,
+                has_side_effect: pass_node.has_side_effect,
+                reads: pass_node
+                    .reads
+                    .iter()
+                    .map(|handle| handle.index())
+                    .collect(),
+                writes: pass_node
+                    .writes
+                    .iter()
+                    .map(|handle| handle.index())
+                    .collect(),
+            })
+            .collect();
+
+        let resources = self
+            .resources
+            .iter()
+            .map(|resource| ResourceExport {
+                index: resource.info.handle.index(),
+                name: resource.info.name.clone(),
+                imported: resource.is_imported(),
+                kept: resource.info.kept,
+                first_pass_node: resource
+                    .info
+                    .first_pass_node_handle
+                    .map(|handle| handle.index()),
+                last_pass_node: resource
+                    .info
+                    .last_pass_node_handle
+                    .map(|handle| handle.index()),
+            })
+            .collect();
+
+        let resource_nodes = self
+            .resource_nodes
+            .iter()
+            .map(|resource_node| ResourceNodeExport {
+                index: resource_node.resource_node_handle().index(),
+                resource: resource_node.resource_handle().index(),
+                version: resource_node.version,
+                writer: resource_node.writer_handle.map(|handle| handle.index()),
+            })
+            .collect();
+
+        FrameGraphExport {
+            compiled,
+            pass_nodes,
+            resources,
+            resource_nodes,
+        }
+    }
+
+    ///检查图中的错误用法，避免在编译和执行时崩溃
+    pub fn validate(&self) -> Vec<GraphDiagnostic> {
+        let mut diagnostics = vec![];
+
+        for resource_node_handle in self.invalid_kept_handles.iter() {
+            diagnostics.push(GraphDiagnostic::InvalidKeptHandle {
+                resource_node_index: resource_node_handle.index(),
+            });
+        }
+
+        let mut names = HashSet::new();
+        for resource in self.resources.iter() {
+            if !names.insert(resource.info.name.as_str()) {
+                diagnostics.push(GraphDiagnostic::DuplicateResourceName {
+                    resource_name: resource.info.name.clone(),
+                });
+            }
+        }
+
+        for pass_node in self.pass_nodes.iter() {
+            let pass_name = &pass_node.name;
+
+            if pass_node.pass.is_none() {
+                diagnostics.push(GraphDiagnostic::MissingPassData {
+                    pass_name: pass_name.clone(),
+                });
+            }
+
+            let is_valid = pass_node
+                .reads
+                .iter()
+                .chain(pass_node.writes.iter())
+                .all(|handle| self.is_valid_resource_node(handle));
+
+            let mut attachments = vec![];
+            for color_attachment in pass_node.color_attachments.iter() {
+                attachments.push(&color_attachment.view);
+                attachments.extend(color_attachment.resolve_target.iter());
+            }
+
+            let is_valid = is_valid
+                && attachments
+                    .iter()
+                    .map(|view| view.resource_handle())
+                    .chain(
+                        pass_node
+                            .depth_stencil_attachment
+                            .iter()
+                            .map(|attachment| attachment.texture.resource_handle()),
+                    )
+                    .all(|handle| handle.index() < self.resources.len());
+
+            if !is_valid {
+                diagnostics.push(GraphDiagnostic::InvalidHandle {
+                    pass_name: pass_name.clone(),
+                });
+                continue;
+            }
+
+            for resource_node_handle in pass_node.reads.iter() {
+                let resource_node = &self.resource_nodes[resource_node_handle.index()];
+                let resource = &self.resources[resource_node.resource_handle().index()];
+
+                if resource_node.writer_handle.is_none() && !resource.is_imported() {
+                    diagnostics.push(GraphDiagnostic::ReadBeforeWrite {
+                        pass_name: pass_name.clone(),
+                        resource_name: resource.info.name.clone(),
+                    });
+                }
+            }
+
+            for resource_node_handle in pass_node.writes.iter() {
+                let resource_node = &self.resource_nodes[resource_node_handle.index()];
+                let resource = &self.resources[resource_node.resource_handle().index()];
+
+                if resource.is_read_only() {
+                    diagnostics.push(GraphDiagnostic::WriteToReadOnlyImport {
+                        pass_name: pass_name.clone(),
+                        resource_name: resource.info.name.clone(),
+                    });
+                }
+            }
+
+            let mut expect_type =
+                |resource_handle: TypeHandle<VirtualResource>,
+                 expected: &'static str,
+                 is_expected: fn(&AnyFGResourceDescriptor) -> bool| {
+                    let resource = &self.resources[resource_handle.index()];
+
+                    if !is_expected(resource.desc()) {
+                        diagnostics.push(GraphDiagnostic::ResourceTypeMismatch {
+                            pass_name: pass_name.clone(),
+                            resource_name: resource.info.name.clone(),
+                            expected,
+                        });
+                    }
+                };
+
+            for view in attachments {
+                match view {
+                    ColorAttachmentView::SwapChain(handle) => {
+                        expect_type(handle.resource_handle(), "SwapChain", |desc| {
+                            matches!(desc, AnyFGResourceDescriptor::SwapChain(_))
+                        });
+                    }
+                    ColorAttachmentView::Texture(handle, _) => {
+                        expect_type(handle.resource_handle(), "Texture", |desc| {
+                            matches!(desc, AnyFGResourceDescriptor::Texture(_))
+                        });
+                    }
+                }
+            }
+
+            if let Some(depth_stencil_attachment) = pass_node.depth_stencil_attachment.as_ref() {
+                expect_type(
+                    depth_stencil_attachment.texture.resource_handle(),
+                    "Texture",
+                    |desc| matches!(desc, AnyFGResourceDescriptor::Texture(_)),
+                );
+            }
+        }
+
+        diagnostics
+    }
+
+    ///管线没有准备好的渲染节点本帧不执行
+    ///按执行顺序跳过管线没有就绪的节点，以及读取了被跳过节点输出的节点
+    fn skip_pending_passes(&mut self, mut pending_pipelines: Vec<CompiledPipelines>) {
+        for pass_node_handle in self.sorted_pass_nodes.clone() {
+            let pass_node = &self.pass_nodes[pass_node_handle.index()];
+            if pass_node.is_culled() {
+                continue;
+            }
+
+            let mut skipped_dependencies: Vec<String> = vec![];
+            for resource_node_handle in pass_node.reads.iter() {
+                let Some(writer_handle) =
+                    self.resource_nodes[resource_node_handle.index()].writer_handle
+                else {
+                    continue;
+                };
+
+                let writer = &self.pass_nodes[writer_handle.index()];
+                if writer.skipped && !skipped_dependencies.contains(&writer.name) {
+                    skipped_dependencies.push(writer.name.clone());
+                }
+            }
+
+            let pending_pipelines =
+                std::mem::take(&mut pending_pipelines[pass_node_handle.index()]);
+            if pending_pipelines.is_empty() && skipped_dependencies.is_empty() {
+                continue;
+            }
+
+            let pass_node = &mut self.pass_nodes[pass_node_handle.index()];
+            pass_node.skipped = true;
+            self.skipped_passes.push(SkippedPass {
+                pass_name: pass_node.name.clone(),
+                pending_pipelines,
+                skipped_dependencies,
+            });
+        }
+    }
+
+    pub fn compile(
+        &mut self,
+        pipeline_provider: &dyn PipelineProvider,
+    ) -> Result<(), RendererError> {
+        self.transient_memory_report = TransientMemoryReport::default();
+
         if self.pass_nodes.is_empty() {
-            return;
+            return Ok(());
         }
 
-        self.sort();
-        //todo cull
+        let diagnostics = self.validate();
+        if !diagnostics.is_empty() {
+            return Err(RendererError::InvalidGraph { diagnostics });
+        }
+
+        let pending_pipelines: Vec<CompiledPipelines> = self
+            .pass_nodes
+            .iter()
+            .map(|pass_node| pass_node.pipelines.pending(pipeline_provider))
+            .collect();
+
+        let structure_hash = self.structure_hash(&pending_pipelines);
+
+        if let Some(compile_cache) = self.compile_cache.take()
+            && compile_cache.structure_hash == structure_hash
+        {
+            self.apply_compile_cache(&compile_cache, pending_pipelines);
+            self.alias_transient_resources(&compile_cache.device_pass_groups);
+            self.compile_cache = Some(compile_cache);
+            self.compile_cache_hit = true;
+
+            return Ok(());
+        }
+
+        self.compile_cache_hit = false;
+
+        self.sort()?;
+        self.cull();
+        self.skip_pending_passes(pending_pipelines);
 
         self.compute_resource_lifetime();
 
-        self.generate_device_passes();
+        let device_pass_groups = self.generate_device_passes();
+        self.alias_transient_resources(&device_pass_groups);
+
+        self.compile_cache = Some(CompileCache {
+            structure_hash,
+            sorted_pass_nodes: self.sorted_pass_nodes.clone(),
+            pass_node_ref_counts: self
+                .pass_nodes
+                .iter()
+                .map(|pass_node| pass_node.ref_count)
+                .collect(),
+            resource_node_ref_counts: self
+                .resource_nodes
+                .iter()
+                .map(|resource_node| resource_node.ref_count)
+                .collect(),
+            resource_infos: self
+                .resources
+                .iter()
+                .map(|resource| resource.info.clone())
+                .collect(),
+            device_pass_groups,
+        });
+
+        Ok(())
+    }
+
+    fn apply_compile_cache(
+        &mut self,
+        compile_cache: &CompileCache,
+        pending_pipelines: Vec<CompiledPipelines>,
+    ) {
+        self.sorted_pass_nodes = compile_cache.sorted_pass_nodes.clone();
+
+        for (pass_node, ref_count) in self
+            .pass_nodes
+            .iter_mut()
+            .zip(compile_cache.pass_node_ref_counts.iter())
+        {
+            pass_node.ref_count = *ref_count;
+        }
+
+        for (resource_node, ref_count) in self
+            .resource_nodes
+            .iter_mut()
+            .zip(compile_cache.resource_node_ref_counts.iter())
+        {
+            resource_node.ref_count = *ref_count;
+        }
+
+        self.skip_pending_passes(pending_pipelines);
+
+        for (resource, info) in self
+            .resources
+            .iter_mut()
+            .zip(compile_cache.resource_infos.iter())
+        {
+            resource.info.first_pass_node_handle = info.first_pass_node_handle;
+            resource.info.last_pass_node_handle = info.last_pass_node_handle;
+        }
+
+        self.assign_resource_requests();
+
+        self.build_device_passes(&compile_cache.device_pass_groups);
     }
 
 
@@ -154,12 +920,53 @@ impl FrameGraph {
         ResourceNodeHandle::new(handle, resource_handle)
     }
 
+    ///保留资源，写入该资源的渲染节点不会被剔除
+    pub fn keep<ResourceType>(&mut self, resource_handle: &ResourceNodeHandle<ResourceType>) {
+        let resource_node_handle = resource_handle.resource_node_handle();
+
+        if !self.is_valid_resource_node(&resource_node_handle) {
+            self.invalid_kept_handles.push(resource_node_handle);
+            return;
+        }
+
+        let resource_handle = self
+            .get_resource_node(&resource_node_handle)
+            .resource_handle();
+        self.get_resource_mut(&resource_handle).info.kept = true;
+    }
+
     pub fn import<ResourceType>(
         &mut self,
         name: &str,
         resource: Arc<ResourceType>,
         desc: ResourceType::Descriptor,
     ) -> ResourceNodeHandle<ResourceType>
+    where
+        ResourceType: ImportToFrameGraph,
+    {
+        self.import_with(name, resource, desc, false)
+    }
+
+    ///导入只读资源，写入它的渲染节点会在校验时报错
+    pub fn import_read_only<ResourceType>(
+        &mut self,
+        name: &str,
+        resource: Arc<ResourceType>,
+        desc: ResourceType::Descriptor,
+    ) -> ResourceNodeHandle<ResourceType>
+    where
+        ResourceType: ImportToFrameGraph,
+    {
+        self.import_with(name, resource, desc, true)
+    }
+
+    fn import_with<ResourceType>(
+        &mut self,
+        name: &str,
+        resource: Arc<ResourceType>,
+        desc: ResourceType::Descriptor,
+        read_only: bool,
+    ) -> ResourceNodeHandle<ResourceType>
     where
         ResourceType: ImportToFrameGraph,
     {
@@ -170,6 +977,7 @@ impl FrameGraph {
             resource_handle,
             imported_resource,
             desc,
+            read_only,
         );
 
         let resource_info = resource.info.clone();
@@ -213,6 +1021,32 @@ impl FrameGraph {
         &mut self.pass_nodes[handle.index()]
     }
 
+    ///句柄是否指向当前图中的资源节点
+    pub fn is_valid_resource_node(&self, handle: &TypeHandle<ResourceNode>) -> bool {
+        self.resource_nodes
+            .get(handle.index())
+            .is_some_and(|resource_node| {
+                resource_node.resource_handle().index() < self.resources.len()
+            })
+    }
+
+    ///同一资源的上一个版本
+    pub fn previous_version(
+        &self,
+        handle: &TypeHandle<ResourceNode>,
+    ) -> Option<TypeHandle<ResourceNode>> {
+        let resource_node = self.resource_nodes.get(handle.index())?;
+        let version = resource_node.version.checked_sub(1)?;
+
+        self.resource_nodes
+            .iter()
+            .find(|previous| {
+                previous.resource_handle() == resource_node.resource_handle()
+                    && previous.version == version
+            })
+            .map(|previous| previous.resource_node_handle())
+    }
+
     pub fn get_resource_node(&self, handle: &TypeHandle<ResourceNode>) -> &ResourceNode {
         &self.resource_nodes[handle.index()]
     }
@@ -254,3 +1088,431 @@ impl FrameGraph {
         handle
     }
 }
+
+#[cfg(test)]
+mod test {
+    use bevy::render::render_resource::{CachedRenderPipelineId, TextureUsages};
+
+    use std::sync::Arc;
+
+    use crate::{
+        ColorAttachment, GraphDiagnostic, LoadOp, PassData, RecordPipelineProvider, RecordTexture,
+        RenderContext, RendererError, ResourceNodeHandle, Texture, TextureInfo, TextureViewInfo,
+        gfx_record::fixture::texture_info,
+    };
+
+    use super::{AnyFGResourceDescriptor, CompiledPipelines, FrameGraph, SkippedPass};
+
+    struct EmptyPass;
+
+    impl PassData for EmptyPass {
+        fn execute(&self, _render_context: &mut RenderContext) -> Result<(), RendererError> {
+            Ok(())
+        }
+    }
+
+    fn pass_names(frame_graph: &FrameGraph) -> Vec<&str> {
+        frame_graph
+            .sorted_pass_nodes
+            .iter()
+            .map(|handle| frame_graph.get_pass_node(handle).name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_by_dependency() {
+        let mut frame_graph = FrameGraph::default();
+        let texture = frame_graph.create("texture", texture_info(1, 1));
+
+        let written = {
+            let mut builder = frame_graph.create_pass_node_builder(2, "writer");
+            let written = builder.write(texture);
+            builder.set_pass(EmptyPass);
+            written
+        };
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(0, "reader");
+            builder.read(ResourceNodeHandle::<Texture>::from(written));
+            builder.set_pass(EmptyPass);
+        }
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(1, "independent");
+            builder.set_pass(EmptyPass);
+        }
+
+        frame_graph.compile(&RecordPipelineProvider).unwrap();
+
+        assert_eq!(
+            pass_names(&frame_graph),
+            ["independent", "writer", "reader"]
+        );
+    }
+
+    #[test]
+    fn test_sort_cycle() {
+        let mut frame_graph = FrameGraph::default();
+        //导入的资源允许读取初始版本
+        let texture = frame_graph.import(
+            "texture",
+            Arc::new(Texture::new(RecordTexture { id: 0 }, texture_info(1, 1))),
+            texture_info(1, 1),
+        );
+
+        let written = {
+            let mut builder = frame_graph.create_pass_node_builder(0, "writer");
+            let written = builder.write(texture.clone());
+            builder.set_pass(EmptyPass);
+            written
+        };
+
+        {
+            //同时读取旧版本和写入者产生的新版本
+            let mut builder = frame_graph.create_pass_node_builder(1, "reader");
+            builder.read(texture);
+            builder.read(ResourceNodeHandle::<Texture>::from(written));
+            builder.set_pass(EmptyPass);
+        }
+
+        let Err(RendererError::PassCycle { pass_names }) =
+            frame_graph.compile(&RecordPipelineProvider)
+        else {
+            panic!("expected a pass cycle");
+        };
+
+        assert_eq!(pass_names, ["writer", "reader"]);
+    }
+
+    #[test]
+    fn test_cull() {
+        let mut frame_graph = FrameGraph::default();
+        let kept = frame_graph.create("kept", texture_info(1, 1));
+        let unused = frame_graph.create("unused", texture_info(1, 1));
+        let intermediate = frame_graph.create("intermediate", texture_info(1, 1));
+        let loaded = frame_graph.create("loaded", texture_info(1, 1));
+        frame_graph.keep(&kept);
+
+        let intermediate = {
+            let mut builder = frame_graph.create_pass_node_builder(0, "intermediate");
+            let written = builder.write(intermediate);
+            builder.set_pass(EmptyPass);
+            ResourceNodeHandle::<Texture>::from(written)
+        };
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(1, "unused");
+            builder.read(intermediate);
+            builder.write(unused);
+            builder.set_pass(EmptyPass);
+        }
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(2, "kept");
+            builder.write(kept);
+            builder.set_pass(EmptyPass);
+        }
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(3, "side_effect");
+            builder.set_has_side_effect(true);
+            builder.set_pass(EmptyPass);
+        }
+
+        //以 Load 方式写入的节点依赖上一版本的写入者，即使 insert_point 更小
+        let loaded = {
+            let mut builder = frame_graph.create_pass_node_builder(5, "clear");
+            let written = builder.write(loaded);
+            builder.add_attachment(ColorAttachment::texture(
+                written.clone(),
+                TextureViewInfo::default(),
+            ));
+            builder.set_pass(EmptyPass);
+            ResourceNodeHandle::<Texture>::from(written)
+        };
+
+        let loaded = {
+            let mut builder = frame_graph.create_pass_node_builder(4, "load");
+            let written = builder.write(loaded);
+            builder.add_attachment(
+                ColorAttachment::texture(written.clone(), TextureViewInfo::default())
+                    .with_load_op(LoadOp::Load),
+            );
+            builder.set_pass(EmptyPass);
+            ResourceNodeHandle::<Texture>::from(written)
+        };
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(6, "present");
+            builder.read(loaded);
+            builder.set_has_side_effect(true);
+            builder.set_pass(EmptyPass);
+        }
+
+        frame_graph.compile(&RecordPipelineProvider).unwrap();
+
+        let culled: Vec<&str> = frame_graph
+            .pass_nodes
+            .iter()
+            .filter(|pass_node| pass_node.is_culled())
+            .map(|pass_node| pass_node.name.as_str())
+            .collect();
+
+        assert_eq!(culled, ["intermediate", "unused"]);
+        assert_eq!(
+            pass_names(&frame_graph)[3..],
+            ["side_effect", "clear", "load", "present"]
+        );
+        //clear 与 load 共用一个渲染通道
+        assert_eq!(frame_graph.device_passes.as_ref().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_export() {
+        let mut frame_graph = FrameGraph::default();
+        let kept = frame_graph.create("kept", texture_info(1, 1));
+        frame_graph.keep(&kept);
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(0, "writer");
+            builder.write(kept);
+            builder.set_pass(EmptyPass);
+        }
+
+        let export = frame_graph.export();
+        assert!(!export.compiled);
+        assert!(!export.pass_nodes[0].culled);
+        assert_eq!(export.resources[0].first_pass_node, None);
+
+        frame_graph.compile(&RecordPipelineProvider).unwrap();
+
+        let export = frame_graph.export();
+        assert!(export.compiled);
+        assert_eq!(export.pass_nodes[0].writes, [1]);
+        assert_eq!(export.resource_nodes[1].version, 1);
+        assert_eq!(export.resources[0].first_pass_node, Some(0));
+        assert_eq!(export.resources[0].last_pass_node, Some(0));
+
+        let dot = export.to_dot();
+        assert!(dot.contains("pass_0 -> resource_1;"));
+        assert!(dot.contains("label=\"kept v1\""));
+
+        let json: serde_json::Value = serde_json::from_str(&export.to_json().unwrap()).unwrap();
+        assert_eq!(json["pass_nodes"][0]["name"], "writer");
+        assert_eq!(json["pass_nodes"][0]["kind"], "Render");
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut frame_graph = FrameGraph::default();
+        let unwritten = frame_graph.create("unwritten", texture_info(1, 1));
+        let read_only = frame_graph.import_read_only(
+            "read_only",
+            Arc::new(Texture::new(RecordTexture { id: 0 }, texture_info(1, 1))),
+            texture_info(1, 1),
+        );
+        frame_graph.create("unwritten", texture_info(1, 1));
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(0, "invalid");
+            builder.read(unwritten);
+            builder.write(read_only);
+        }
+
+        let Err(RendererError::InvalidGraph { diagnostics }) =
+            frame_graph.compile(&RecordPipelineProvider)
+        else {
+            panic!("expected an invalid graph");
+        };
+
+        assert_eq!(
+            diagnostics,
+            [
+                GraphDiagnostic::DuplicateResourceName {
+                    resource_name: "unwritten".to_string(),
+                },
+                GraphDiagnostic::MissingPassData {
+                    pass_name: "invalid".to_string(),
+                },
+                GraphDiagnostic::ReadBeforeWrite {
+                    pass_name: "invalid".to_string(),
+                    resource_name: "unwritten".to_string(),
+                },
+                GraphDiagnostic::WriteToReadOnlyImport {
+                    pass_name: "invalid".to_string(),
+                    resource_name: "read_only".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_stale_handle() {
+        let mut stale_graph = FrameGraph::default();
+        stale_graph.create("first", texture_info(1, 1));
+        let stale = stale_graph.create("stale", texture_info(1, 1));
+
+        let mut frame_graph = FrameGraph::default();
+        frame_graph.keep(&stale);
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(0, "stale");
+            builder.write(stale);
+            builder.set_pass(EmptyPass);
+        }
+
+        assert_eq!(
+            frame_graph.validate(),
+            [
+                GraphDiagnostic::InvalidKeptHandle {
+                    resource_node_index: 1,
+                },
+                GraphDiagnostic::InvalidHandle {
+                    pass_name: "stale".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_skip_pending_pipelines() {
+        let mut frame_graph = FrameGraph::default();
+        let first = frame_graph.create("first", texture_info(1, 1));
+        let second = frame_graph.create("second", texture_info(1, 1));
+        let intermediate = frame_graph.create("intermediate", texture_info(1, 1));
+        frame_graph.keep(&first);
+        frame_graph.keep(&second);
+
+        let intermediate = {
+            let mut builder = frame_graph.create_pass_node_builder(0, "pending");
+            let written = builder.write(intermediate);
+            builder.add_render_pipeline(CachedRenderPipelineId::INVALID);
+            builder.set_pass(EmptyPass);
+            ResourceNodeHandle::<Texture>::from(written)
+        };
+
+        //读取被跳过节点的输出，需要一并跳过
+        {
+            let mut builder = frame_graph.create_pass_node_builder(1, "reader");
+            builder.read(intermediate);
+            builder.write(first);
+            builder.set_pass(EmptyPass);
+        }
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(2, "ready");
+            builder.write(second);
+            builder.set_pass(EmptyPass);
+        }
+
+        frame_graph.compile(&RecordPipelineProvider).unwrap();
+
+        assert_eq!(
+            frame_graph.skipped_passes(),
+            [
+                SkippedPass {
+                    pass_name: "pending".to_string(),
+                    pending_pipelines: CompiledPipelines {
+                        render_pipeline_ids: vec![CachedRenderPipelineId::INVALID],
+                        compute_pipeline_ids: vec![],
+                    },
+                    skipped_dependencies: vec![],
+                },
+                SkippedPass {
+                    pass_name: "reader".to_string(),
+                    pending_pipelines: CompiledPipelines::default(),
+                    skipped_dependencies: vec!["pending".to_string()],
+                },
+            ]
+        );
+        assert_eq!(frame_graph.device_passes.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_alias_transient_resources() {
+        let mut frame_graph = FrameGraph::default();
+        let a = frame_graph.create("a", texture_info(1, 1));
+        let b = frame_graph.create(
+            "b",
+            TextureInfo {
+                usage: TextureUsages::STORAGE_BINDING,
+                ..texture_info(1, 1)
+            },
+        );
+        let c = frame_graph.create("c", texture_info(1, 1));
+
+        let a = {
+            let mut builder = frame_graph.create_pass_node_builder(0, "write_a");
+            let written = builder.write(a);
+            builder.set_pass(EmptyPass);
+            ResourceNodeHandle::<Texture>::from(written)
+        };
+
+        let c = {
+            let mut builder = frame_graph.create_pass_node_builder(1, "read_a");
+            builder.read(a);
+            let written = builder.write(c);
+            builder.set_pass(EmptyPass);
+            ResourceNodeHandle::<Texture>::from(written)
+        };
+
+        let b = {
+            let mut builder = frame_graph.create_pass_node_builder(2, "write_b");
+            let written = builder.write(b);
+            builder.set_pass(EmptyPass);
+            ResourceNodeHandle::<Texture>::from(written)
+        };
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(3, "present");
+            builder.read(b);
+            builder.read(c);
+            builder.set_has_side_effect(true);
+            builder.set_pass(EmptyPass);
+        }
+
+        frame_graph.compile(&RecordPipelineProvider).unwrap();
+
+        //a 与 b 生命周期不重叠，共用一个槽，c 与两者都重叠
+        let report = frame_graph.transient_memory_report();
+        assert_eq!(report.slot_count, 2);
+        assert_eq!(report.unaliased_bytes, 12);
+        assert_eq!(report.peak_bytes, 8);
+
+        let a_desc = frame_graph.resources[0].desc().clone();
+        assert_eq!(a_desc, *frame_graph.resources[1].desc());
+        assert_ne!(a_desc, *frame_graph.resources[2].desc());
+
+        let AnyFGResourceDescriptor::Texture(a_desc) = a_desc else {
+            panic!("expected texture descriptor");
+        };
+        assert_eq!(
+            a_desc.usage,
+            TextureUsages::RENDER_ATTACHMENT | TextureUsages::STORAGE_BINDING
+        );
+    }
+
+    #[test]
+    fn test_structure_hash_read_write_split() {
+        let mut frame_graph = FrameGraph::default();
+        let a = frame_graph.create("a", texture_info(1, 1));
+        let b = frame_graph.create("b", texture_info(1, 1));
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(0, "pass");
+            builder.read(a);
+            builder.write(b);
+            builder.set_pass(EmptyPass);
+        }
+
+        let pending_pipelines = vec![CompiledPipelines::default()];
+        let hash = frame_graph.structure_hash(&pending_pipelines);
+
+        //读写拼接起来相同，但划分不同
+        let pass_node = &mut frame_graph.pass_nodes[0];
+        let handle = pass_node.reads.pop().unwrap();
+        pass_node.writes.insert(0, handle);
+
+        assert_ne!(hash, frame_graph.structure_hash(&pending_pipelines));
+    }
+}
@@ -1,25 +1,33 @@
 pub mod device_pass;
+pub mod diagnostic;
+pub mod export;
 pub mod graph;
 pub mod handle;
 pub mod pass;
 pub mod pass_node;
 pub mod pass_node_builder;
+pub mod pipeline_provider;
 pub mod render_context;
 pub mod resource;
 pub mod resource_board;
 pub mod resource_node;
 pub mod resource_table;
+pub mod transient_allocator;
 pub mod transient_resource_cache;
 
 pub use device_pass::*;
+pub use diagnostic::*;
+pub use export::*;
 pub use graph::*;
 pub use handle::*;
 pub use pass::*;
 pub use pass_node::*;
 pub use pass_node_builder::*;
+pub use pipeline_provider::*;
 pub use render_context::*;
 pub use resource::*;
 pub use resource_board::*;
 pub use resource_node::*;
 pub use resource_table::*;
+pub use transient_allocator::*;
 pub use transient_resource_cache::*;
@@ -1,27 +1,47 @@
 use std::marker::PhantomData;
 
-use crate::ColorAttachment;
+use serde::Serialize;
+
+use crate::{ColorAttachment, DepthStencilAttachment, DepthStencilTexture, LoadOp};
 
 use super::{
     DynPass,
-    graph::FrameGraph,
+    graph::{CompiledPipelines, FrameGraph},
     handle::TypeHandle,
     resource::VirtualResource,
     resource_node::{ResourceNode, ResourceNodeHandle},
 };
 
+///渲染节点在设备上开启的通道类型
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum PassKind {
+    #[default]
+    Render,
+    Compute,
+}
+
 pub struct PassNode {
     pub name: String,
     pub handle: TypeHandle<PassNode>,
     pub writes: Vec<TypeHandle<ResourceNode>>,
     pub reads: Vec<TypeHandle<ResourceNode>>,
     pub insert_point: usize,
+    pub kind: PassKind,
     ///使用资源的获取生命周期
     pub resource_request_array: Vec<TypeHandle<VirtualResource>>,
     ///使用资源的释放生命周期
     pub resource_release_array: Vec<TypeHandle<VirtualResource>>,
     pub pass: Option<DynPass>,
     pub color_attachments: Vec<ColorAttachment>,
+    pub depth_stencil_attachment: Option<DepthStencilAttachment>,
+    ///写入的资源节点中仍被使用的数量，为0时渲染节点会被剔除
+    pub ref_count: u32,
+    ///有副作用的渲染节点不会被剔除
+    pub has_side_effect: bool,
+    ///执行前需要编译完成的管线
+    pub pipelines: CompiledPipelines,
+    ///管线没有准备好，本帧跳过
+    pub skipped: bool,
 }
 
 impl PassNode {
@@ -32,22 +52,76 @@ impl PassNode {
             writes: vec![],
             reads: vec![],
             insert_point,
+            kind: PassKind::default(),
             resource_request_array: vec![],
             resource_release_array: vec![],
             pass: None,
             color_attachments: vec![],
+            depth_stencil_attachment: None,
+            ref_count: 0,
+            has_side_effect: false,
+            pipelines: CompiledPipelines::default(),
+            skipped: false,
         }
     }
 
+    pub fn should_execute(&self) -> bool {
+        !self.is_culled() && !self.skipped
+    }
+
+    pub fn is_culled(&self) -> bool {
+        self.ref_count == 0 && !self.has_side_effect
+    }
+
     pub fn add_attachment(&mut self, color_attachment: ColorAttachment) {
         self.color_attachments.push(color_attachment);
     }
 
+    ///以 Load 方式使用的附件需要读取写入前的版本
+    pub fn load_reads(&self, graph: &FrameGraph) -> Vec<TypeHandle<ResourceNode>> {
+        let mut handles = vec![];
+
+        for color_attachment in self.color_attachments.iter() {
+            if matches!(color_attachment.ops.load, LoadOp::Load) {
+                handles.push(color_attachment.view.resource_node_handle());
+            }
+        }
+
+        if let Some(DepthStencilAttachment {
+            texture: DepthStencilTexture::Write(handle),
+            depth_ops,
+            stencil_ops,
+        }) = self.depth_stencil_attachment.as_ref()
+        {
+            let is_load = depth_ops.is_some_and(|ops| matches!(ops.load, LoadOp::Load))
+                || stencil_ops.is_some_and(|ops| matches!(ops.load, LoadOp::Load));
+
+            if is_load {
+                handles.push(handle.resource_node_handle());
+            }
+        }
+
+        handles
+            .iter()
+            .filter_map(|handle| graph.previous_version(handle))
+            .collect()
+    }
+
     pub fn write<ResourceType>(
         &mut self,
         graph: &mut FrameGraph,
         resource_handle: ResourceNodeHandle<ResourceType>,
     ) -> ResourceNodeRef<ResourceType, GpuWrite> {
+        //无效的句柄原样记录，交给 FrameGraph::validate 报告
+        if !graph.is_valid_resource_node(&resource_handle.resource_node_handle()) {
+            self.writes.push(resource_handle.resource_node_handle());
+
+            return ResourceNodeRef::new(
+                resource_handle.resource_node_handle(),
+                resource_handle.resource_handle(),
+            );
+        }
+
         let resource_handle = graph
             .get_resource_node(&resource_handle.resource_node_handle())
             .resource_handle();
@@ -76,6 +150,10 @@ impl PassNode {
             self.reads.push(resource_node_handle);
         }
 
+        if !graph.is_valid_resource_node(&resource_node_handle) {
+            return ResourceNodeRef::new(resource_node_handle, resource_handle.resource_handle());
+        }
+
         let resource_handle = graph
             .get_resource_node(&resource_node_handle)
             .resource_handle();
@@ -121,6 +199,15 @@ impl<ResourceType, ViewType> ResourceNodeRef<ResourceType, ViewType> {
     }
 }
 
+///写入得到的新版本可以交给后面的节点继续读写
+impl<ResourceType> From<ResourceNodeRef<ResourceType, GpuWrite>>
+    for ResourceNodeHandle<ResourceType>
+{
+    fn from(value: ResourceNodeRef<ResourceType, GpuWrite>) -> Self {
+        ResourceNodeHandle::new(value.handle, value.resource_handle)
+    }
+}
+
 pub trait GpuViewType: 'static {}
 
 #[derive(Debug)]
@@ -128,6 +215,7 @@ pub struct GpuRead;
 
 impl GpuViewType for GpuRead {}
 
+#[derive(Debug)]
 pub struct GpuWrite;
 
 impl GpuViewType for GpuWrite {}
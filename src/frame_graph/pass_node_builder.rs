@@ -1,11 +1,13 @@
 use std::sync::Arc;
 
-use crate::ColorAttachment;
+use bevy::render::render_resource::{CachedComputePipelineId, CachedRenderPipelineId};
+
+use crate::{ColorAttachment, DepthStencilAttachment};
 
 use super::{
     ImportToFrameGraph, PassData,
     graph::FrameGraph,
-    pass_node::{GpuRead, GpuWrite, PassNode, ResourceNodeRef},
+    pass_node::{GpuRead, GpuWrite, PassKind, PassNode, ResourceNodeRef},
     resource::{FGResource, FGResourceDescriptor, TypeEquals},
     resource_node::ResourceNodeHandle,
 };
@@ -37,12 +39,59 @@ impl<'a> PassNodeBuilder<'a> {
             .add_attachment(color_attachment);
     }
 
+    pub fn set_depth_stencil_attachment(
+        &mut self,
+        depth_stencil_attachment: DepthStencilAttachment,
+    ) {
+        self.pass_node.as_mut().unwrap().depth_stencil_attachment = Some(depth_stencil_attachment);
+    }
+
+    pub fn set_kind(&mut self, kind: PassKind) {
+        self.pass_node.as_mut().unwrap().kind = kind;
+    }
+
+    ///有副作用的渲染节点即使写入的资源没有被使用也不会被剔除
+    pub fn set_has_side_effect(&mut self, has_side_effect: bool) {
+        self.pass_node.as_mut().unwrap().has_side_effect = has_side_effect;
+    }
+
+    pub fn keep<ResourceType>(&mut self, resource_handle: &ResourceNodeHandle<ResourceType>) {
+        self.graph.keep(resource_handle);
+    }
+
+    ///声明渲染节点使用的管线，没有编译完成时该节点会被跳过
+    pub fn add_render_pipeline(&mut self, id: CachedRenderPipelineId) {
+        self.pass_node
+            .as_mut()
+            .unwrap()
+            .pipelines
+            .render_pipeline_ids
+            .push(id);
+    }
+
+    pub fn add_compute_pipeline(&mut self, id: CachedComputePipelineId) {
+        self.pass_node
+            .as_mut()
+            .unwrap()
+            .pipelines
+            .compute_pipeline_ids
+            .push(id);
+    }
+
     pub fn set_pass<T: PassData>(&mut self, pass: T) {
         self.pass_node.as_mut().unwrap().pass = Some(Box::new(pass));
     }
 
     fn build(&mut self) {
-        let pass_node = self.pass_node.take().unwrap();
+        let mut pass_node = self.pass_node.take().unwrap();
+
+        //Load 附件隐式读取上一版本，保证写入它的节点先执行且不会被剔除
+        for resource_node_handle in pass_node.load_reads(self.graph) {
+            if !pass_node.reads.contains(&resource_node_handle) {
+                pass_node.reads.push(resource_node_handle);
+            }
+        }
+
         self.graph.add_pass_node(pass_node);
     }
 
@@ -69,6 +118,18 @@ impl<'a> PassNodeBuilder<'a> {
         self.graph.import(name, resource, desc)
     }
 
+    pub fn import_read_only<ResourceType>(
+        &mut self,
+        name: &str,
+        resource: Arc<ResourceType>,
+        desc: ResourceType::Descriptor,
+    ) -> ResourceNodeHandle<ResourceType>
+    where
+        ResourceType: ImportToFrameGraph,
+    {
+        self.graph.import_read_only(name, resource, desc)
+    }
+
     pub fn write<ResourceType>(
         &mut self,
         resource_handle: ResourceNodeHandle<ResourceType>,
@@ -0,0 +1,19 @@
+use bevy::render::render_resource::{
+    CachedComputePipelineId, CachedRenderPipelineId, ComputePipeline, PipelineCache, RenderPipeline,
+};
+
+pub trait PipelineProvider: 'static + Sync + Send {
+    fn get_render_pipeline(&self, id: CachedRenderPipelineId) -> Option<&RenderPipeline>;
+
+    fn get_compute_pipeline(&self, id: CachedComputePipelineId) -> Option<&ComputePipeline>;
+}
+
+impl PipelineProvider for PipelineCache {
+    fn get_render_pipeline(&self, id: CachedRenderPipelineId) -> Option<&RenderPipeline> {
+        PipelineCache::get_render_pipeline(self, id)
+    }
+
+    fn get_compute_pipeline(&self, id: CachedComputePipelineId) -> Option<&ComputePipeline> {
+        PipelineCache::get_compute_pipeline(self, id)
+    }
+}
@@ -1,18 +1,23 @@
 use std::{ops::Range, sync::Arc};
 
-use bevy::render::render_resource::{CachedRenderPipelineId, PipelineCache};
+use bevy::render::render_resource::{CachedComputePipelineId, CachedRenderPipelineId};
 
-use crate::{CommandBuffer, Device};
+use crate::{Buffer, CommandBuffer, Device, RendererError};
 
-use super::{FGResource, GpuRead, ResourceNodeRef, ResourceTable, TransientResourceCache};
+use super::{
+    FGResource, GpuRead, GpuViewType, PipelineProvider, ResourceNodeRef, ResourceTable,
+    TransientResourceCache,
+};
 
 pub struct RenderContext<'a> {
     pub(crate) device: &'a Arc<Device>,
     pub(crate) resource_table: ResourceTable,
     pub(crate) transient_resource_cache: &'a mut TransientResourceCache,
     pub(crate) cb: Option<CommandBuffer>,
-    pub(crate) pipeline_cache: &'a PipelineCache,
+    pub(crate) pipeline_cache: &'a dyn PipelineProvider,
     pub(crate) queue_cbs: Vec<CommandBuffer>,
+    ///执行失败的渲染节点名称及其错误
+    pub(crate) failures: Vec<(String, RendererError)>,
 }
 
 impl<'a> RenderContext<'a> {
@@ -26,16 +31,59 @@ impl<'a> RenderContext<'a> {
         }
     }
 
-    pub fn set_render_pipeline(&mut self, id: CachedRenderPipelineId) -> Option<()> {
-        if let Some(render_pipeline) = self.pipeline_cache.get_render_pipeline(id) {
-            if let Some(cb) = self.cb.as_mut() {
-                cb.set_render_pipeline(render_pipeline);
-            }
+    pub fn dispatch_workgroups(&mut self, x: u32, y: u32, z: u32) {
+        if let Some(cb) = self.cb.as_mut() {
+            cb.dispatch_workgroups(x, y, z);
+        }
+    }
+
+    pub fn dispatch_workgroups_indirect(
+        &mut self,
+        indirect_buffer: &ResourceNodeRef<Buffer, GpuRead>,
+        indirect_offset: u64,
+    ) -> Result<(), RendererError> {
+        let resource_handle = indirect_buffer.resource_handle();
+        let indirect_buffer = self
+            .resource_table
+            .get_resource::<Buffer>(&resource_handle)
+            .ok_or(RendererError::MissingResource {
+                resource_index: resource_handle.index(),
+            })?;
+
+        if let Some(cb) = self.cb.as_mut() {
+            cb.dispatch_workgroups_indirect(indirect_buffer, indirect_offset)?;
+        }
+
+        Ok(())
+    }
 
-            Some(())
-        } else {
-            None
+    pub fn set_compute_pipeline(
+        &mut self,
+        id: CachedComputePipelineId,
+    ) -> Result<(), RendererError> {
+        let compute_pipeline = self
+            .pipeline_cache
+            .get_compute_pipeline(id)
+            .ok_or(RendererError::ComputePipelineNotReady(id))?;
+
+        if let Some(cb) = self.cb.as_mut() {
+            cb.set_compute_pipeline(compute_pipeline);
         }
+
+        Ok(())
+    }
+
+    pub fn set_render_pipeline(&mut self, id: CachedRenderPipelineId) -> Result<(), RendererError> {
+        let render_pipeline = self
+            .pipeline_cache
+            .get_render_pipeline(id)
+            .ok_or(RendererError::RenderPipelineNotReady(id))?;
+
+        if let Some(cb) = self.cb.as_mut() {
+            cb.set_render_pipeline(render_pipeline);
+        }
+
+        Ok(())
     }
 
     pub fn set_cb(&mut self, cb: CommandBuffer) {
@@ -46,10 +94,24 @@ impl<'a> RenderContext<'a> {
         self.cb.take()
     }
 
+    ///没有正在录制的命令缓冲时创建一个，整张图共用
+    pub fn get_or_create_cb(&mut self) -> &mut CommandBuffer {
+        self.cb
+            .get_or_insert_with(|| self.device.create_command_buffer())
+    }
+
+    ///结束当前命令缓冲的录制并放入提交队列
+    pub fn flush(&mut self) {
+        if let Some(mut command_buffer) = self.cb.take() {
+            command_buffer.finish();
+            self.queue_cbs.push(command_buffer);
+        }
+    }
+
     pub fn new(
         device: &'a Arc<Device>,
         transient_resource_cache: &'a mut TransientResourceCache,
-        pipeline_cache: &'a PipelineCache,
+        pipeline_cache: &'a dyn PipelineProvider,
     ) -> Self {
         RenderContext {
             device,
@@ -58,13 +120,19 @@ impl<'a> RenderContext<'a> {
             cb: None,
             pipeline_cache,
             queue_cbs: vec![],
+            failures: vec![],
         }
     }
 
-    pub fn get_resource<ResourceType: FGResource>(
+    ///资源不存在或类型不一致时返回 MissingResource
+    pub fn get_resource<ResourceType: FGResource, ViewType: GpuViewType>(
         &self,
-        handle: &ResourceNodeRef<ResourceType, GpuRead>,
-    ) -> Option<&ResourceType> {
-        self.resource_table.get_resource(&handle.resource_handle())
+        handle: &ResourceNodeRef<ResourceType, ViewType>,
+    ) -> Result<&ResourceType, RendererError> {
+        self.resource_table
+            .get_resource(&handle.resource_handle())
+            .ok_or(RendererError::MissingResource {
+                resource_index: handle.resource_handle().index(),
+            })
     }
 }
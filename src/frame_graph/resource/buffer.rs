@@ -0,0 +1,36 @@
+use std::sync::Arc;
+
+use crate::{Buffer, BufferInfo};
+
+use super::{
+    AnyFGResource, AnyFGResourceDescriptor, FGResource, FGResourceDescriptor, ImportToFrameGraph,
+    ImportedResource,
+};
+
+impl ImportToFrameGraph for Buffer {
+    fn import(self: Arc<Self>) -> ImportedResource {
+        ImportedResource::Buffer(self)
+    }
+}
+
+impl FGResource for Buffer {
+    type Descriptor = BufferInfo;
+
+    fn borrow_resource(res: &AnyFGResource) -> Option<&Self> {
+        match res {
+            AnyFGResource::OwnedBuffer(res) => Some(res),
+            AnyFGResource::ImportedBuffer(res) => Some(res),
+            _ => None,
+        }
+    }
+}
+
+impl From<BufferInfo> for AnyFGResourceDescriptor {
+    fn from(value: BufferInfo) -> Self {
+        AnyFGResourceDescriptor::Buffer(value)
+    }
+}
+
+impl FGResourceDescriptor for BufferInfo {
+    type Resource = Buffer;
+}
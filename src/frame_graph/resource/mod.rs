@@ -1,15 +1,26 @@
+mod buffer;
 mod swap_chain;
 mod texture;
 
 use std::{fmt::Debug, sync::Arc};
 
-use crate::{Device, SwapChain, SwapChainInfo, Texture, TextureInfo};
+use crate::{Buffer, BufferInfo, Device, SwapChain, SwapChainInfo, Texture, TextureInfo};
 
 use super::{handle::TypeHandle, pass_node::PassNode};
 
 impl Device {
-    pub fn create(&self, _desc: &AnyFGResourceDescriptor) -> AnyFGResource {
-        todo!()
+    pub fn create(&self, desc: &AnyFGResourceDescriptor) -> AnyFGResource {
+        match desc {
+            AnyFGResourceDescriptor::Texture(desc) => {
+                AnyFGResource::OwnedTexture(self.create_texture(desc))
+            }
+            AnyFGResourceDescriptor::Buffer(desc) => {
+                AnyFGResource::OwnedBuffer(self.create_buffer(desc))
+            }
+            AnyFGResourceDescriptor::SwapChain(_) => {
+                unreachable!("swap chain can only be imported")
+            }
+        }
     }
 }
 
@@ -38,6 +49,7 @@ impl VirtualResource {
         handle: TypeHandle<VirtualResource>,
         resource: ImportedResource,
         desc: ResourceType::Descriptor,
+        read_only: bool,
     ) -> VirtualResource {
         let info = ResourceInfo::new(name, handle);
 
@@ -45,10 +57,32 @@ impl VirtualResource {
             state: ResourceState::Imported(ImportedResourceState {
                 desc: desc.into(),
                 resource,
+                read_only,
             }),
             info,
         }
     }
+
+    pub fn is_imported(&self) -> bool {
+        matches!(self.state, ResourceState::Imported(_))
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self.state,
+            ResourceState::Imported(ImportedResourceState {
+                read_only: true,
+                ..
+            })
+        )
+    }
+
+    pub fn desc(&self) -> &AnyFGResourceDescriptor {
+        match &self.state {
+            ResourceState::Imported(state) => &state.desc,
+            ResourceState::Setup(desc) => desc,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -63,6 +97,8 @@ pub struct ResourceInfo {
     pub first_pass_node_handle: Option<TypeHandle<PassNode>>,
     ///最后使用此资源的渲染节点
     pub last_pass_node_handle: Option<TypeHandle<PassNode>>,
+    ///保留的资源不会因为没有读取者而被剔除
+    pub kept: bool,
 }
 
 impl ResourceInfo {
@@ -73,6 +109,7 @@ impl ResourceInfo {
             version: 0,
             first_pass_node_handle: None,
             last_pass_node_handle: None,
+            kept: false,
         }
     }
 
@@ -97,11 +134,14 @@ impl ResourceInfo {
 pub struct ImportedResourceState {
     pub desc: AnyFGResourceDescriptor,
     pub resource: ImportedResource,
+    ///只读的导入资源不允许渲染节点写入
+    pub read_only: bool,
 }
 
 #[derive(Clone)]
 pub enum ImportedResource {
     Texture(Arc<Texture>),
+    Buffer(Arc<Buffer>),
     SwapChain(Arc<SwapChain>),
 }
 
@@ -115,19 +155,23 @@ pub enum ResourceState {
 pub enum AnyFGResource {
     OwnedTexture(Texture),
     ImportedTexture(Arc<Texture>),
+    OwnedBuffer(Buffer),
+    ImportedBuffer(Arc<Buffer>),
     ImportedSwapChain(Arc<SwapChain>),
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum AnyFGResourceDescriptor {
     Texture(TextureInfo),
+    Buffer(BufferInfo),
     SwapChain(SwapChainInfo),
 }
 
 pub trait FGResource: 'static + Debug {
     type Descriptor: FGResourceDescriptor;
 
-    fn borrow_resource(res: &AnyFGResource) -> &Self;
+    ///资源类型不一致时返回None
+    fn borrow_resource(res: &AnyFGResource) -> Option<&Self>;
 }
 
 pub trait FGResourceDescriptor: 'static + Clone + Debug + Into<AnyFGResourceDescriptor> {
@@ -16,12 +16,10 @@ impl ImportToFrameGraph for SwapChain {
 impl FGResource for SwapChain {
     type Descriptor = SwapChainInfo;
 
-    fn borrow_resource(res: &AnyFGResource) -> &Self {
+    fn borrow_resource(res: &AnyFGResource) -> Option<&Self> {
         match res {
-            AnyFGResource::ImportedSwapChain(res) => res,
-            _ => {
-                unimplemented!()
-            }
+            AnyFGResource::ImportedSwapChain(res) => Some(res),
+            _ => None,
         }
     }
 }
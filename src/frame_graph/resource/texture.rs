@@ -1,17 +1,26 @@
+use std::sync::Arc;
+
 use crate::{Texture, TextureInfo};
 
-use super::{AnyFGResource, AnyFGResourceDescriptor, FGResource, FGResourceDescriptor};
+use super::{
+    AnyFGResource, AnyFGResourceDescriptor, FGResource, FGResourceDescriptor, ImportToFrameGraph,
+    ImportedResource,
+};
+
+impl ImportToFrameGraph for Texture {
+    fn import(self: Arc<Self>) -> ImportedResource {
+        ImportedResource::Texture(self)
+    }
+}
 
 impl FGResource for Texture {
     type Descriptor = TextureInfo;
 
-    fn borrow_resource(res: &AnyFGResource) -> &Self {
+    fn borrow_resource(res: &AnyFGResource) -> Option<&Self> {
         match res {
-            AnyFGResource::OwnedTexture(res) => res,
-            AnyFGResource::ImportedTexture(res) => res,
-            _ => {
-                unimplemented!()
-            }
+            AnyFGResource::OwnedTexture(res) => Some(res),
+            AnyFGResource::ImportedTexture(res) => Some(res),
+            _ => None,
         }
     }
 }
@@ -9,6 +9,16 @@ pub struct ResourceNodeHandle<ResourceType> {
     _marker: PhantomData<ResourceType>,
 }
 
+impl<ResourceType> Clone for ResourceNodeHandle<ResourceType> {
+    fn clone(&self) -> Self {
+        Self {
+            handle: self.handle,
+            resource_handle: self.resource_handle,
+            _marker: PhantomData,
+        }
+    }
+}
+
 pub struct RawResourceNodeHandle {
     handle: TypeHandle<ResourceNode>,
     resource_handle: TypeHandle<VirtualResource>,
@@ -57,6 +67,8 @@ pub struct ResourceNode {
     resource_handle: TypeHandle<VirtualResource>,
     pub version: u32,
     pub writer_handle: Option<TypeHandle<PassNode>>,
+    ///读取此资源节点的渲染节点数量
+    pub ref_count: u32,
 }
 
 impl ResourceNode {
@@ -70,6 +82,7 @@ impl ResourceNode {
             resource_handle,
             version,
             writer_handle: None,
+            ref_count: 0,
         }
     }
 
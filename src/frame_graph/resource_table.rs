@@ -19,7 +19,7 @@ impl ResourceTable {
     ) -> Option<&ResourceType> {
         self.resources
             .get(handle)
-            .map(|any| ResourceType::borrow_resource(any))
+            .and_then(|any| ResourceType::borrow_resource(any))
     }
 
     pub fn request_resources(
@@ -35,6 +35,9 @@ impl ResourceTable {
                 ImportedResource::Texture(resource) => {
                     AnyFGResource::ImportedTexture(resource.clone())
                 }
+                ImportedResource::Buffer(resource) => {
+                    AnyFGResource::ImportedBuffer(resource.clone())
+                }
                 ImportedResource::SwapChain(resource) => {
                     AnyFGResource::ImportedSwapChain(resource.clone())
                 }
@@ -44,6 +47,10 @@ impl ResourceTable {
                     .get_image(texture_desc)
                     .map(AnyFGResource::OwnedTexture)
                     .unwrap_or_else(|| device.create(desc)),
+                AnyFGResourceDescriptor::Buffer(buffer_desc) => transient_resource_cache
+                    .get_buffer(buffer_desc)
+                    .map(AnyFGResource::OwnedBuffer)
+                    .unwrap_or_else(|| device.create(desc)),
                 _ => return,
             },
         };
@@ -62,6 +69,10 @@ impl ResourceTable {
                 AnyFGResource::OwnedTexture(texture) => {
                     transient_resource_cache.insert_image(texture.get_desc().clone(), texture);
                 }
+                AnyFGResource::ImportedBuffer(_) => {}
+                AnyFGResource::OwnedBuffer(buffer) => {
+                    transient_resource_cache.insert_buffer(buffer.get_desc().clone(), buffer);
+                }
                 AnyFGResource::ImportedSwapChain(_) => {}
             }
         }
@@ -0,0 +1,159 @@
+use crate::{BufferInfo, TextureInfo};
+
+use super::AnyFGResourceDescriptor;
+
+///一帧中临时资源占用的显存
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TransientMemoryReport {
+    ///别名复用后需要的显存
+    pub peak_bytes: u64,
+    ///不做别名复用时需要的显存
+    pub unaliased_bytes: u64,
+    pub slot_count: usize,
+}
+
+struct AllocationSlot {
+    desc: AnyFGResourceDescriptor,
+    ///最后使用此槽的 DevicePass 索引
+    last_device_pass: usize,
+}
+
+///为生命周期不重叠且描述兼容的临时资源分配同一个槽
+#[derive(Default)]
+pub struct TransientAllocator {
+    slots: Vec<AllocationSlot>,
+    unaliased_bytes: u64,
+}
+
+impl TransientAllocator {
+    ///资源需要按首次使用的顺序分配，返回槽的索引
+    pub fn allocate(
+        &mut self,
+        desc: &AnyFGResourceDescriptor,
+        first_device_pass: usize,
+        last_device_pass: usize,
+    ) -> usize {
+        self.unaliased_bytes += estimate_size(desc);
+
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.last_device_pass >= first_device_pass {
+                continue;
+            }
+
+            if let Some(merged) = merge_desc(&slot.desc, desc) {
+                slot.desc = merged;
+                slot.last_device_pass = last_device_pass;
+                return index;
+            }
+        }
+
+        self.slots.push(AllocationSlot {
+            desc: desc.clone(),
+            last_device_pass,
+        });
+
+        self.slots.len() - 1
+    }
+
+    pub fn slot_desc(&self, index: usize) -> &AnyFGResourceDescriptor {
+        &self.slots[index].desc
+    }
+
+    pub fn report(&self) -> TransientMemoryReport {
+        TransientMemoryReport {
+            peak_bytes: self
+                .slots
+                .iter()
+                .map(|slot| estimate_size(&slot.desc))
+                .sum(),
+            unaliased_bytes: self.unaliased_bytes,
+            slot_count: self.slots.len(),
+        }
+    }
+}
+
+///合并两个兼容的描述，不兼容时返回None
+fn merge_desc(
+    a: &AnyFGResourceDescriptor,
+    b: &AnyFGResourceDescriptor,
+) -> Option<AnyFGResourceDescriptor> {
+    match (a, b) {
+        (AnyFGResourceDescriptor::Texture(a), AnyFGResourceDescriptor::Texture(b)) => {
+            merge_texture_desc(a, b).map(AnyFGResourceDescriptor::Texture)
+        }
+        (AnyFGResourceDescriptor::Buffer(a), AnyFGResourceDescriptor::Buffer(b)) => {
+            merge_buffer_desc(a, b).map(AnyFGResourceDescriptor::Buffer)
+        }
+        _ => None,
+    }
+}
+
+///尺寸和格式必须一致，用途和视图格式取并集
+fn merge_texture_desc(a: &TextureInfo, b: &TextureInfo) -> Option<TextureInfo> {
+    if a.size != b.size
+        || a.mip_level_count != b.mip_level_count
+        || a.sample_count != b.sample_count
+        || a.dimension != b.dimension
+        || a.format != b.format
+    {
+        return None;
+    }
+
+    let mut view_formats = a.view_formats.clone();
+    for view_format in b.view_formats.iter() {
+        if !view_formats.contains(view_format) {
+            view_formats.push(*view_format);
+        }
+    }
+
+    Some(TextureInfo {
+        usage: a.usage | b.usage,
+        view_formats,
+        ..a.clone()
+    })
+}
+
+///可映射的缓冲区对用途有限制，不参与复用
+fn merge_buffer_desc(a: &BufferInfo, b: &BufferInfo) -> Option<BufferInfo> {
+    let mappable = wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::MAP_WRITE;
+
+    if a.mapped_at_creation
+        || b.mapped_at_creation
+        || a.usage.intersects(mappable)
+        || b.usage.intersects(mappable)
+    {
+        return None;
+    }
+
+    Some(BufferInfo {
+        size: a.size.max(b.size),
+        usage: a.usage | b.usage,
+        mapped_at_creation: false,
+    })
+}
+
+///估算资源占用的字节数
+pub fn estimate_size(desc: &AnyFGResourceDescriptor) -> u64 {
+    match desc {
+        AnyFGResourceDescriptor::Texture(desc) => estimate_texture_size(desc),
+        AnyFGResourceDescriptor::Buffer(desc) => desc.size,
+        AnyFGResourceDescriptor::SwapChain(_) => 0,
+    }
+}
+
+pub fn estimate_texture_size(desc: &TextureInfo) -> u64 {
+    let (block_width, block_height) = desc.format.block_dimensions();
+    //深度模板格式没有统一的块大小，按 4 字节估算
+    let block_size = desc.format.block_copy_size(None).unwrap_or(4) as u64;
+
+    (0..desc.mip_level_count)
+        .map(|mip_level| {
+            let size = desc.size.mip_level_size(mip_level, desc.dimension);
+            let blocks_x = size.width.div_ceil(block_width) as u64;
+            let blocks_y = size.height.div_ceil(block_height) as u64;
+
+            blocks_x * blocks_y * size.depth_or_array_layers as u64 * block_size
+        })
+        .sum::<u64>()
+        * desc.sample_count as u64
+}
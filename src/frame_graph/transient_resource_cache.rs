@@ -2,27 +2,266 @@ use std::collections::HashMap;
 
 use bevy::ecs::resource::Resource;
 
-use crate::{Texture, TextureInfo};
+use crate::{Buffer, BufferInfo, Texture, TextureInfo};
 
-#[derive(Default, Debug, Resource)]
+use super::estimate_texture_size;
+
+///默认连续未使用多少帧后释放
+pub const DEFAULT_MAX_UNUSED_FRAMES: u64 = 3;
+
+#[derive(Debug)]
+struct CachedResource<T> {
+    resource: T,
+    ///最后一次放回缓存时的帧数
+    last_used_frame: u64,
+}
+
+#[derive(Debug, Resource)]
 pub struct TransientResourceCache {
-    textures: HashMap<TextureInfo, Vec<Texture>>,
+    textures: HashMap<TextureInfo, Vec<CachedResource<Texture>>>,
+    buffers: HashMap<BufferInfo, Vec<CachedResource<Buffer>>>,
+    frame: u64,
+    max_unused_frames: u64,
+    ///缓存占用的显存上限，超出时先释放最久未使用的资源
+    memory_budget: Option<u64>,
+}
+
+impl Default for TransientResourceCache {
+    fn default() -> Self {
+        Self {
+            textures: Default::default(),
+            buffers: Default::default(),
+            frame: 0,
+            max_unused_frames: DEFAULT_MAX_UNUSED_FRAMES,
+            memory_budget: None,
+        }
+    }
 }
 
 impl TransientResourceCache {
-    pub fn get_image(&mut self, desc: &TextureInfo) -> Option<Texture> {
-        if let Some(entry) = self.textures.get_mut(desc) {
-            entry.pop()
-        } else {
-            None
+    pub fn with_max_unused_frames(mut self, max_unused_frames: u64) -> Self {
+        self.max_unused_frames = max_unused_frames;
+        self
+    }
+
+    pub fn with_memory_budget(mut self, memory_budget: u64) -> Self {
+        self.memory_budget = Some(memory_budget);
+        self
+    }
+
+    pub fn set_max_unused_frames(&mut self, max_unused_frames: u64) {
+        self.max_unused_frames = max_unused_frames;
+    }
+
+    pub fn set_memory_budget(&mut self, memory_budget: Option<u64>) {
+        self.memory_budget = memory_budget;
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    ///缓存中资源估算占用的字节数
+    pub fn memory_usage(&self) -> u64 {
+        let textures: u64 = self
+            .textures
+            .iter()
+            .map(|(desc, entry)| estimate_texture_size(desc) * entry.len() as u64)
+            .sum();
+        let buffers: u64 = self
+            .buffers
+            .iter()
+            .map(|(desc, entry)| desc.size * entry.len() as u64)
+            .sum();
+
+        textures + buffers
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.values().map(Vec::len).sum::<usize>()
+            + self.buffers.values().map(Vec::len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    ///每帧结束时调用，推进帧数并按策略释放资源
+    pub fn advance_frame(&mut self) {
+        self.frame += 1;
+        self.trim();
+    }
+
+    ///释放超过 max_unused_frames 未使用的资源，再按显存上限释放最久未使用的资源
+    pub fn trim(&mut self) {
+        let frame = self.frame;
+        let max_unused_frames = self.max_unused_frames;
+        let is_alive = |last_used_frame: u64| frame - last_used_frame <= max_unused_frames;
+
+        for entry in self.textures.values_mut() {
+            entry.retain(|cached| is_alive(cached.last_used_frame));
+        }
+        self.textures.retain(|_, entry| !entry.is_empty());
+
+        for entry in self.buffers.values_mut() {
+            entry.retain(|cached| is_alive(cached.last_used_frame));
+        }
+        self.buffers.retain(|_, entry| !entry.is_empty());
+
+        if let Some(memory_budget) = self.memory_budget {
+            self.trim_to(memory_budget);
         }
     }
 
+    ///释放最久未使用的资源，直到估算占用不超过 memory_budget
+    pub fn trim_to(&mut self, memory_budget: u64) {
+        let mut usage = self.memory_usage();
+
+        while usage > memory_budget {
+            let oldest_texture = self
+                .textures
+                .iter()
+                .filter_map(|(desc, entry)| {
+                    entry
+                        .first()
+                        .map(|cached| (cached.last_used_frame, desc.clone()))
+                })
+                .min_by_key(|(last_used_frame, _)| *last_used_frame);
+            let oldest_buffer = self
+                .buffers
+                .iter()
+                .filter_map(|(desc, entry)| {
+                    entry
+                        .first()
+                        .map(|cached| (cached.last_used_frame, desc.clone()))
+                })
+                .min_by_key(|(last_used_frame, _)| *last_used_frame);
+
+            match (oldest_texture, oldest_buffer) {
+                (Some((texture_frame, desc)), buffer)
+                    if buffer
+                        .as_ref()
+                        .is_none_or(|(frame, _)| texture_frame <= *frame) =>
+                {
+                    usage -= estimate_texture_size(&desc);
+                    remove_oldest(&mut self.textures, &desc);
+                }
+                (_, Some((_, desc))) => {
+                    usage -= desc.size;
+                    remove_oldest(&mut self.buffers, &desc);
+                }
+                _ => break,
+            }
+        }
+    }
+
+    ///释放缓存中的所有资源
+    pub fn clear(&mut self) {
+        self.textures.clear();
+        self.buffers.clear();
+    }
+
+    pub fn get_image(&mut self, desc: &TextureInfo) -> Option<Texture> {
+        take_latest(&mut self.textures, desc)
+    }
+
     pub fn insert_image(&mut self, desc: TextureInfo, resource: Texture) {
-        if let Some(entry) = self.textures.get_mut(&desc) {
-            entry.push(resource);
-        } else {
-            self.textures.insert(desc, vec![resource]);
+        let cached = CachedResource {
+            resource,
+            last_used_frame: self.frame,
+        };
+
+        self.textures.entry(desc).or_default().push(cached);
+    }
+
+    pub fn get_buffer(&mut self, desc: &BufferInfo) -> Option<Buffer> {
+        take_latest(&mut self.buffers, desc)
+    }
+
+    pub fn insert_buffer(&mut self, desc: BufferInfo, resource: Buffer) {
+        let cached = CachedResource {
+            resource,
+            last_used_frame: self.frame,
+        };
+
+        self.buffers.entry(desc).or_default().push(cached);
+    }
+}
+
+///同一描述下按放回顺序排列，优先取最近使用的资源
+fn take_latest<K: std::hash::Hash + Eq, T>(
+    resources: &mut HashMap<K, Vec<CachedResource<T>>>,
+    desc: &K,
+) -> Option<T> {
+    resources
+        .get_mut(desc)
+        .and_then(|entry| entry.pop())
+        .map(|cached| cached.resource)
+}
+
+fn remove_oldest<K: std::hash::Hash + Eq, T>(
+    resources: &mut HashMap<K, Vec<CachedResource<T>>>,
+    desc: &K,
+) {
+    if let Some(entry) = resources.get_mut(desc) {
+        entry.remove(0);
+
+        if entry.is_empty() {
+            resources.remove(desc);
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use bevy::render::render_resource::BufferUsages;
+
+    use crate::{BufferInfo, Device, RecordDevice, gfx_record::fixture::texture_info};
+
+    use super::TransientResourceCache;
+
+    #[test]
+    fn test_evict_unused() {
+        let device = Arc::new(Device::new(RecordDevice::default()));
+        let mut cache = TransientResourceCache::default().with_max_unused_frames(1);
+
+        let old = texture_info(4, 1);
+        cache.insert_image(old.clone(), device.create_texture(&old));
+        cache.advance_frame();
+
+        let new = texture_info(8, 1);
+        cache.insert_image(new.clone(), device.create_texture(&new));
+        cache.advance_frame();
+
+        //old 已经连续两帧没有使用
+        assert!(cache.get_image(&old).is_none());
+        assert!(cache.get_image(&new).is_some());
+    }
+
+    #[test]
+    fn test_memory_budget() {
+        let device = Arc::new(Device::new(RecordDevice::default()));
+        let mut cache = TransientResourceCache::default().with_memory_budget(64);
+
+        let texture = texture_info(4, 1);
+        cache.insert_image(texture.clone(), device.create_texture(&texture));
+        cache.advance_frame();
+
+        let buffer = BufferInfo {
+            size: 64,
+            usage: BufferUsages::UNIFORM,
+            mapped_at_creation: false,
+        };
+        cache.insert_buffer(buffer.clone(), device.create_buffer(&buffer));
+        assert_eq!(cache.memory_usage(), 80);
+
+        cache.trim();
+        assert_eq!(cache.memory_usage(), 64);
+        assert!(cache.get_buffer(&buffer).is_some());
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+}
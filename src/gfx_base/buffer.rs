@@ -0,0 +1,19 @@
+use bevy::render::render_resource::BufferUsages;
+use downcast_rs::Downcast;
+use std::fmt::Debug;
+
+use crate::define_gfx_frame_graph_type;
+
+pub trait BufferTrait: 'static + Debug + Sync + Send {}
+pub trait ErasedBufferTrait: 'static + Downcast + Debug + Sync + Send {}
+
+impl<T: BufferTrait> ErasedBufferTrait for T {}
+
+define_gfx_frame_graph_type!(Buffer, BufferTrait, ErasedBufferTrait, BufferInfo);
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct BufferInfo {
+    pub size: u64,
+    pub usage: BufferUsages,
+    pub mapped_at_creation: bool,
+}
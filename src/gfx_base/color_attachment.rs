@@ -1,14 +1,113 @@
-use crate::frame_graph::{GpuRead, ResourceNodeRef};
+use bevy::color::LinearRgba;
 
-use super::SwapChain;
+use crate::frame_graph::{
+    GpuWrite, RenderContext, ResourceNode, ResourceNodeRef, TypeHandle, VirtualResource,
+};
+
+use super::{LoadOp, Operations, StoreOp, SwapChain, Texture, TextureView, TextureViewInfo};
+
+#[derive(Clone, Debug)]
+pub enum ColorAttachmentView {
+    SwapChain(ResourceNodeRef<SwapChain, GpuWrite>),
+    Texture(ResourceNodeRef<Texture, GpuWrite>, TextureViewInfo),
+}
+
+impl ColorAttachmentView {
+    pub fn resource_handle(&self) -> TypeHandle<VirtualResource> {
+        match self {
+            ColorAttachmentView::SwapChain(handle) => handle.resource_handle(),
+            ColorAttachmentView::Texture(handle, _) => handle.resource_handle(),
+        }
+    }
+
+    pub fn resource_node_handle(&self) -> TypeHandle<ResourceNode> {
+        match self {
+            ColorAttachmentView::SwapChain(handle) => handle.resource_node_handle(),
+            ColorAttachmentView::Texture(handle, _) => handle.resource_node_handle(),
+        }
+    }
+
+    ///是否为同一资源的同一视图
+    pub fn is_same_view(&self, other: &ColorAttachmentView) -> bool {
+        match (self, other) {
+            (ColorAttachmentView::SwapChain(a), ColorAttachmentView::SwapChain(b)) => {
+                a.resource_handle() == b.resource_handle()
+            }
+            (
+                ColorAttachmentView::Texture(a, a_view_info),
+                ColorAttachmentView::Texture(b, b_view_info),
+            ) => a.resource_handle() == b.resource_handle() && a_view_info == b_view_info,
+            _ => false,
+        }
+    }
+
+    pub fn get_texture_view(&self, render_context: &RenderContext) -> Option<TextureView> {
+        match self {
+            ColorAttachmentView::SwapChain(handle) => render_context
+                .get_resource::<SwapChain, _>(handle)
+                .ok()
+                .map(|resource| resource.get_texture_view()),
+            ColorAttachmentView::Texture(handle, view_info) => render_context
+                .get_resource::<Texture, _>(handle)
+                .ok()
+                .map(|resource| resource.get_texture_view(view_info)),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
-pub enum ColorAttachment {
-    SwapChain(ResourceNodeRef<SwapChain, GpuRead>),
+pub struct ColorAttachment {
+    pub view: ColorAttachmentView,
+    ///多重采样附件的解析目标，同样需要在图中记录写入
+    pub resolve_target: Option<ColorAttachmentView>,
+    pub ops: Operations<LinearRgba>,
 }
 
 impl ColorAttachment {
-    pub fn swap_chain(handle: ResourceNodeRef<SwapChain, GpuRead>) -> Self {
-        ColorAttachment::SwapChain(handle)
+    pub fn swap_chain(handle: ResourceNodeRef<SwapChain, GpuWrite>) -> Self {
+        ColorAttachment {
+            view: ColorAttachmentView::SwapChain(handle),
+            resolve_target: None,
+            ops: Operations {
+                load: LoadOp::Clear(LinearRgba::BLACK),
+                store: StoreOp::Store,
+            },
+        }
+    }
+
+    pub fn texture(handle: ResourceNodeRef<Texture, GpuWrite>, view_info: TextureViewInfo) -> Self {
+        ColorAttachment {
+            view: ColorAttachmentView::Texture(handle, view_info),
+            resolve_target: None,
+            ops: Operations {
+                load: LoadOp::Clear(LinearRgba::BLACK),
+                store: StoreOp::Store,
+            },
+        }
+    }
+
+    pub fn is_same_target(&self, other: &ColorAttachment) -> bool {
+        let is_same_resolve_target = match (&self.resolve_target, &other.resolve_target) {
+            (None, None) => true,
+            (Some(a), Some(b)) => a.is_same_view(b),
+            _ => false,
+        };
+
+        self.view.is_same_view(&other.view) && is_same_resolve_target
+    }
+
+    pub fn with_resolve_target(mut self, resolve_target: ColorAttachmentView) -> Self {
+        self.resolve_target = Some(resolve_target);
+        self
+    }
+
+    pub fn with_load_op(mut self, load: LoadOp<LinearRgba>) -> Self {
+        self.ops.load = load;
+        self
+    }
+
+    pub fn with_store_op(mut self, store: StoreOp) -> Self {
+        self.ops.store = store;
+        self
     }
 }
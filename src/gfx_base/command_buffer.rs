@@ -1,69 +1,180 @@
 use std::{fmt::Debug, ops::Range};
 
-use bevy::render::render_resource::RenderPipeline;
+use bevy::render::render_resource::{ComputePipeline, RenderPipeline};
 use downcast_rs::Downcast;
 
-use crate::define_gfx_type;
+use crate::{RendererError, define_gfx_type};
 
-use super::{Device, RenderPass};
+use super::{Buffer, Device, RenderPass};
 
 pub trait CommandBufferTrait: 'static + Sync + Send + Debug {
-    fn begin_render_pass(&mut self, device: &Device, render_pass: RenderPass);
+    fn begin_render_pass(
+        &mut self,
+        device: &Device,
+        render_pass: RenderPass,
+    ) -> Result<(), RendererError>;
 
     fn end_render_pass(&mut self);
 
+    fn begin_compute_pass(&mut self, device: &Device) -> Result<(), RendererError>;
+
+    fn end_compute_pass(&mut self);
+
     fn set_render_pipeline(&mut self, render_pipeline: &RenderPipeline);
 
+    fn set_compute_pipeline(&mut self, compute_pipeline: &ComputePipeline);
+
     fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>);
+
+    fn dispatch_workgroups(&mut self, x: u32, y: u32, z: u32);
+
+    fn dispatch_workgroups_indirect(
+        &mut self,
+        indirect_buffer: &Buffer,
+        indirect_offset: u64,
+    ) -> Result<(), RendererError>;
+
+    ///结束录制，之后只能提交
+    fn finish(&mut self);
 }
 
 pub trait ErasedCommandBufferTrait: 'static + Sync + Send + Debug + Downcast {
-    fn begin_render_pass(&mut self, device: &Device, render_pass: RenderPass);
+    fn begin_render_pass(
+        &mut self,
+        device: &Device,
+        render_pass: RenderPass,
+    ) -> Result<(), RendererError>;
 
     fn end_render_pass(&mut self);
 
+    fn begin_compute_pass(&mut self, device: &Device) -> Result<(), RendererError>;
+
+    fn end_compute_pass(&mut self);
+
     fn set_render_pipeline(&mut self, render_pipeline: &RenderPipeline);
 
+    fn set_compute_pipeline(&mut self, compute_pipeline: &ComputePipeline);
+
     fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>);
+
+    fn dispatch_workgroups(&mut self, x: u32, y: u32, z: u32);
+
+    fn dispatch_workgroups_indirect(
+        &mut self,
+        indirect_buffer: &Buffer,
+        indirect_offset: u64,
+    ) -> Result<(), RendererError>;
+
+    ///结束录制，之后只能提交
+    fn finish(&mut self);
 }
 
 impl<T> ErasedCommandBufferTrait for T
 where
     T: CommandBufferTrait,
 {
-    fn begin_render_pass(&mut self, device: &Device, render_pass: RenderPass) {
-        <T as CommandBufferTrait>::begin_render_pass(self, device, render_pass);
+    fn begin_render_pass(
+        &mut self,
+        device: &Device,
+        render_pass: RenderPass,
+    ) -> Result<(), RendererError> {
+        <T as CommandBufferTrait>::begin_render_pass(self, device, render_pass)
     }
 
     fn end_render_pass(&mut self) {
         <T as CommandBufferTrait>::end_render_pass(self);
     }
 
+    fn begin_compute_pass(&mut self, device: &Device) -> Result<(), RendererError> {
+        <T as CommandBufferTrait>::begin_compute_pass(self, device)
+    }
+
+    fn end_compute_pass(&mut self) {
+        <T as CommandBufferTrait>::end_compute_pass(self);
+    }
+
     fn set_render_pipeline(&mut self, render_pipeline: &RenderPipeline) {
         <T as CommandBufferTrait>::set_render_pipeline(self, render_pipeline);
     }
 
+    fn set_compute_pipeline(&mut self, compute_pipeline: &ComputePipeline) {
+        <T as CommandBufferTrait>::set_compute_pipeline(self, compute_pipeline);
+    }
+
     fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>) {
         <T as CommandBufferTrait>::draw(self, vertices, instances);
     }
+
+    fn dispatch_workgroups(&mut self, x: u32, y: u32, z: u32) {
+        <T as CommandBufferTrait>::dispatch_workgroups(self, x, y, z);
+    }
+
+    fn dispatch_workgroups_indirect(
+        &mut self,
+        indirect_buffer: &Buffer,
+        indirect_offset: u64,
+    ) -> Result<(), RendererError> {
+        <T as CommandBufferTrait>::dispatch_workgroups_indirect(
+            self,
+            indirect_buffer,
+            indirect_offset,
+        )
+    }
+
+    fn finish(&mut self) {
+        <T as CommandBufferTrait>::finish(self);
+    }
 }
 
 define_gfx_type!(CommandBuffer, CommandBufferTrait, ErasedCommandBufferTrait);
 
 impl CommandBuffer {
-    pub fn begin_render_pass(&mut self, device: &Device, render_pass: RenderPass) {
-        self.value.begin_render_pass(device, render_pass);
+    pub fn begin_render_pass(
+        &mut self,
+        device: &Device,
+        render_pass: RenderPass,
+    ) -> Result<(), RendererError> {
+        self.value.begin_render_pass(device, render_pass)
     }
 
     pub fn end_render_pass(&mut self) {
         self.value.end_render_pass();
     }
 
+    pub fn begin_compute_pass(&mut self, device: &Device) -> Result<(), RendererError> {
+        self.value.begin_compute_pass(device)
+    }
+
+    pub fn end_compute_pass(&mut self) {
+        self.value.end_compute_pass();
+    }
+
     pub fn set_render_pipeline(&mut self, render_pipeline: &RenderPipeline) {
         self.value.set_render_pipeline(render_pipeline);
     }
 
+    pub fn set_compute_pipeline(&mut self, compute_pipeline: &ComputePipeline) {
+        self.value.set_compute_pipeline(compute_pipeline);
+    }
+
     pub fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>) {
         self.value.draw(vertices, instances);
     }
+
+    pub fn dispatch_workgroups(&mut self, x: u32, y: u32, z: u32) {
+        self.value.dispatch_workgroups(x, y, z);
+    }
+
+    pub fn dispatch_workgroups_indirect(
+        &mut self,
+        indirect_buffer: &Buffer,
+        indirect_offset: u64,
+    ) -> Result<(), RendererError> {
+        self.value
+            .dispatch_workgroups_indirect(indirect_buffer, indirect_offset)
+    }
+
+    pub fn finish(&mut self) {
+        self.value.finish();
+    }
 }
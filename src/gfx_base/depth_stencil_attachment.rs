@@ -0,0 +1,78 @@
+use crate::frame_graph::{
+    GpuRead, GpuWrite, RenderContext, ResourceNode, ResourceNodeRef, TypeHandle, VirtualResource,
+};
+
+use super::{Operations, Texture, TextureView, TextureViewInfo};
+
+#[derive(Clone, Debug)]
+pub enum DepthStencilTexture {
+    Write(ResourceNodeRef<Texture, GpuWrite>),
+    ///只读的深度模板附件只需要在图中记录读取
+    ReadOnly(ResourceNodeRef<Texture, GpuRead>),
+}
+
+impl DepthStencilTexture {
+    pub fn resource_handle(&self) -> TypeHandle<VirtualResource> {
+        match self {
+            DepthStencilTexture::Write(handle) => handle.resource_handle(),
+            DepthStencilTexture::ReadOnly(handle) => handle.resource_handle(),
+        }
+    }
+
+    pub fn resource_node_handle(&self) -> TypeHandle<ResourceNode> {
+        match self {
+            DepthStencilTexture::Write(handle) => handle.resource_node_handle(),
+            DepthStencilTexture::ReadOnly(handle) => handle.resource_node_handle(),
+        }
+    }
+
+    pub fn get_texture_view(&self, render_context: &RenderContext) -> Option<TextureView> {
+        let texture = match self {
+            DepthStencilTexture::Write(handle) => render_context.get_resource::<Texture, _>(handle),
+            DepthStencilTexture::ReadOnly(handle) => {
+                render_context.get_resource::<Texture, _>(handle)
+            }
+        };
+
+        texture
+            .ok()
+            .map(|texture| texture.get_texture_view(&TextureViewInfo::default()))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DepthStencilAttachment {
+    pub texture: DepthStencilTexture,
+    ///为None时深度只读
+    pub depth_ops: Option<Operations<f32>>,
+    ///为None时模板只读
+    pub stencil_ops: Option<Operations<u32>>,
+}
+
+impl DepthStencilAttachment {
+    pub fn new(handle: ResourceNodeRef<Texture, GpuWrite>, depth_ops: Operations<f32>) -> Self {
+        DepthStencilAttachment {
+            texture: DepthStencilTexture::Write(handle),
+            depth_ops: Some(depth_ops),
+            stencil_ops: None,
+        }
+    }
+
+    pub fn read_only(handle: ResourceNodeRef<Texture, GpuRead>) -> Self {
+        DepthStencilAttachment {
+            texture: DepthStencilTexture::ReadOnly(handle),
+            depth_ops: None,
+            stencil_ops: None,
+        }
+    }
+
+    pub fn is_same_target(&self, other: &DepthStencilAttachment) -> bool {
+        std::mem::discriminant(&self.texture) == std::mem::discriminant(&other.texture)
+            && self.texture.resource_handle() == other.texture.resource_handle()
+    }
+
+    pub fn with_stencil_ops(mut self, stencil_ops: Operations<u32>) -> Self {
+        self.stencil_ops = Some(stencil_ops);
+        self
+    }
+}
@@ -1,25 +1,33 @@
-use crate::define_gfx_type;
+use crate::{RendererError, define_gfx_type};
 use std::fmt::Debug;
 
 use bevy::ecs::resource::Resource;
 use downcast_rs::Downcast;
 
-use super::{CommandBuffer, RenderPass, RenderPassInfo};
+use super::{Buffer, BufferInfo, CommandBuffer, RenderPass, RenderPassInfo, Texture, TextureInfo};
 
 pub trait DeviceTrait: 'static + Sync + Send + Debug {
     fn create_command_buffer(&self) -> CommandBuffer;
 
+    fn create_texture(&self, desc: &TextureInfo) -> Texture;
+
+    fn create_buffer(&self, desc: &BufferInfo) -> Buffer;
+
     fn create_render_pass(&self, desc: &RenderPassInfo) -> RenderPass;
 
-    fn submit(&self, command_buffers: Vec<CommandBuffer>);
+    fn submit(&self, command_buffers: Vec<CommandBuffer>) -> Result<(), RendererError>;
 }
 
 pub trait ErasedDeviceTrait: 'static + Sync + Send + Downcast + Debug {
     fn create_command_buffer(&self) -> CommandBuffer;
 
+    fn create_texture(&self, desc: &TextureInfo) -> Texture;
+
+    fn create_buffer(&self, desc: &BufferInfo) -> Buffer;
+
     fn create_render_pass(&self, desc: &RenderPassInfo) -> RenderPass;
 
-    fn submit(&self, command_buffers: Vec<CommandBuffer>);
+    fn submit(&self, command_buffers: Vec<CommandBuffer>) -> Result<(), RendererError>;
 }
 
 impl<T: DeviceTrait> ErasedDeviceTrait for T {
@@ -27,11 +35,19 @@ impl<T: DeviceTrait> ErasedDeviceTrait for T {
         <T as DeviceTrait>::create_command_buffer(self)
     }
 
+    fn create_texture(&self, desc: &TextureInfo) -> Texture {
+        <T as DeviceTrait>::create_texture(self, desc)
+    }
+
+    fn create_buffer(&self, desc: &BufferInfo) -> Buffer {
+        <T as DeviceTrait>::create_buffer(self, desc)
+    }
+
     fn create_render_pass(&self, desc: &RenderPassInfo) -> RenderPass {
         <T as DeviceTrait>::create_render_pass(self, desc)
     }
 
-    fn submit(&self, command_buffers: Vec<CommandBuffer>) {
+    fn submit(&self, command_buffers: Vec<CommandBuffer>) -> Result<(), RendererError> {
         <T as DeviceTrait>::submit(self, command_buffers)
     }
 }
@@ -45,11 +61,19 @@ impl Device {
         self.value.create_command_buffer()
     }
 
+    pub fn create_texture(&self, desc: &TextureInfo) -> Texture {
+        self.value.create_texture(desc)
+    }
+
+    pub fn create_buffer(&self, desc: &BufferInfo) -> Buffer {
+        self.value.create_buffer(desc)
+    }
+
     pub fn create_render_pass(&self, desc: &RenderPassInfo) -> RenderPass {
         self.value.create_render_pass(desc)
     }
 
-    pub fn submit(&self, command_buffers: Vec<CommandBuffer>) {
-        self.value.submit(command_buffers);
+    pub fn submit(&self, command_buffers: Vec<CommandBuffer>) -> Result<(), RendererError> {
+        self.value.submit(command_buffers)
     }
 }
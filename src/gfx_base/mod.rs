@@ -1,16 +1,22 @@
+mod buffer;
 mod color_attachment;
 mod command_buffer;
+mod depth_stencil_attachment;
 
 mod device;
 mod macros;
+mod operations;
 mod render_pass;
 mod swap_chain;
 mod texture;
 mod texture_view;
 
+pub use buffer::*;
 pub use color_attachment::*;
 pub use command_buffer::*;
+pub use depth_stencil_attachment::*;
 pub use device::*;
+pub use operations::*;
 pub use render_pass::*;
 pub use swap_chain::*;
 pub use texture::*;
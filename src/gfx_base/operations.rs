@@ -0,0 +1,19 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoadOp<V> {
+    Clear(V),
+    Load,
+    ///不关心附件的初始内容
+    DontCare,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreOp {
+    Store,
+    Discard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Operations<V> {
+    pub load: LoadOp<V>,
+    pub store: StoreOp,
+}
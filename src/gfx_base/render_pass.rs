@@ -4,11 +4,12 @@ use std::fmt::Debug;
 
 use crate::frame_graph::RenderContext;
 
-use super::ColorAttachment;
+use super::{ColorAttachment, DepthStencilAttachment};
 
 #[derive(Default, Clone, Debug)]
 pub struct RenderPassInfo {
     pub color_attachments: Vec<ColorAttachment>,
+    pub depth_stencil_attachment: Option<DepthStencilAttachment>,
 }
 
 impl RenderPassInfo {
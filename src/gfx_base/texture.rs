@@ -1,14 +1,40 @@
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
 use downcast_rs::Downcast;
 use std::fmt::Debug;
 
 use crate::define_gfx_frame_graph_type;
 
-pub trait TextureTrait: 'static + Debug + Sync + Send {}
-pub trait ErasedTextureTrait: 'static + Downcast + Debug + Sync + Send {}
+use super::{TextureView, TextureViewInfo};
 
-impl<T: TextureTrait> ErasedTextureTrait for T {}
+pub trait TextureTrait: 'static + Debug + Sync + Send {
+    fn get_texture_view(&self, info: &TextureViewInfo) -> TextureView;
+}
+
+pub trait ErasedTextureTrait: 'static + Downcast + Debug + Sync + Send {
+    fn get_texture_view(&self, info: &TextureViewInfo) -> TextureView;
+}
+
+impl<T: TextureTrait> ErasedTextureTrait for T {
+    fn get_texture_view(&self, info: &TextureViewInfo) -> TextureView {
+        <T as TextureTrait>::get_texture_view(self, info)
+    }
+}
 
 define_gfx_frame_graph_type!(Texture, TextureTrait, ErasedTextureTrait, TextureInfo);
 
+impl Texture {
+    pub fn get_texture_view(&self, info: &TextureViewInfo) -> TextureView {
+        self.value.get_texture_view(info)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-pub struct TextureInfo;
+pub struct TextureInfo {
+    pub size: Extent3d,
+    pub mip_level_count: u32,
+    pub sample_count: u32,
+    pub dimension: TextureDimension,
+    pub format: TextureFormat,
+    pub usage: TextureUsages,
+    pub view_formats: Vec<TextureFormat>,
+}
@@ -1,3 +1,4 @@
+use bevy::render::render_resource::{TextureAspect, TextureFormat, TextureViewDimension};
 use downcast_rs::Downcast;
 use std::fmt::Debug;
 
@@ -9,3 +10,29 @@ pub trait ErasedTextureViewTrait: 'static + Downcast + Debug {}
 impl<T: TextureViewTrait> ErasedTextureViewTrait for T {}
 
 define_gfx_type!(TextureView, TextureViewTrait, ErasedTextureViewTrait);
+
+///默认值为覆盖整个纹理的视图
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+pub struct TextureViewInfo {
+    pub format: Option<TextureFormat>,
+    pub dimension: Option<TextureViewDimension>,
+    pub aspect: TextureAspect,
+    pub base_mip_level: u32,
+    pub mip_level_count: Option<u32>,
+    pub base_array_layer: u32,
+    pub array_layer_count: Option<u32>,
+}
+
+impl TextureViewInfo {
+    ///只包含单个mip层级和数组层的二维视图
+    pub fn single(mip_level: u32, array_layer: u32) -> Self {
+        TextureViewInfo {
+            dimension: Some(TextureViewDimension::D2),
+            base_mip_level: mip_level,
+            mip_level_count: Some(1),
+            base_array_layer: array_layer,
+            array_layer_count: Some(1),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,8 @@
+use crate::BufferTrait;
+
+#[derive(Debug)]
+pub struct RecordBuffer {
+    pub id: usize,
+}
+
+impl BufferTrait for RecordBuffer {}
@@ -0,0 +1,128 @@
+use std::ops::Range;
+
+use bevy::render::render_resource::{ComputePipeline, RenderPipeline};
+
+use crate::{
+    RendererError,
+    gfx_base::{Buffer, CommandBufferTrait, Device, RenderPass, TextureView},
+};
+
+use super::{RecordBuffer, RecordCommand, RecordLog, RecordRenderPass, RecordTextureView};
+
+fn view_name(texture_view: &TextureView) -> Result<String, RendererError> {
+    texture_view
+        .downcast_ref::<RecordTextureView>()
+        .map(|texture_view| texture_view.0.clone())
+        .ok_or_else(|| RendererError::backend("texture view is not a RecordTextureView"))
+}
+
+#[derive(Debug)]
+pub struct RecordCommandBuffer {
+    log: RecordLog,
+}
+
+impl RecordCommandBuffer {
+    pub fn new(log: RecordLog) -> Self {
+        RecordCommandBuffer { log }
+    }
+}
+
+impl CommandBufferTrait for RecordCommandBuffer {
+    fn begin_render_pass(
+        &mut self,
+        _device: &Device,
+        render_pass: RenderPass,
+    ) -> Result<(), RendererError> {
+        let mut render_pass = render_pass
+            .downcast::<RecordRenderPass>()
+            .ok_or_else(|| RendererError::backend("render pass is not a RecordRenderPass"))?;
+
+        let color_attachments = render_pass
+            .texture_views
+            .take()
+            .ok_or_else(|| RendererError::backend("render pass has no texture views"))?
+            .iter()
+            .flatten()
+            .map(view_name)
+            .collect::<Result<_, _>>()?;
+
+        let resolve_targets = render_pass
+            .resolve_target_views
+            .take()
+            .ok_or_else(|| RendererError::backend("render pass has no resolve target views"))?
+            .iter()
+            .flatten()
+            .map(view_name)
+            .collect::<Result<_, _>>()?;
+
+        let depth_stencil_attachment = render_pass
+            .depth_stencil_view
+            .as_ref()
+            .map(view_name)
+            .transpose()?;
+
+        self.log.push(RecordCommand::BeginRenderPass {
+            color_attachments,
+            resolve_targets,
+            depth_stencil_attachment,
+        });
+
+        Ok(())
+    }
+
+    fn end_render_pass(&mut self) {
+        self.log.push(RecordCommand::EndRenderPass);
+    }
+
+    fn begin_compute_pass(&mut self, _device: &Device) -> Result<(), RendererError> {
+        self.log.push(RecordCommand::BeginComputePass);
+
+        Ok(())
+    }
+
+    fn end_compute_pass(&mut self) {
+        self.log.push(RecordCommand::EndComputePass);
+    }
+
+    fn finish(&mut self) {
+        self.log.push(RecordCommand::FinishCommandBuffer);
+    }
+
+    fn set_compute_pipeline(&mut self, compute_pipeline: &ComputePipeline) {
+        self.log
+            .push(RecordCommand::SetComputePipeline(compute_pipeline.id()));
+    }
+
+    fn dispatch_workgroups(&mut self, x: u32, y: u32, z: u32) {
+        self.log.push(RecordCommand::DispatchWorkgroups { x, y, z });
+    }
+
+    fn dispatch_workgroups_indirect(
+        &mut self,
+        indirect_buffer: &Buffer,
+        indirect_offset: u64,
+    ) -> Result<(), RendererError> {
+        let indirect_buffer = indirect_buffer
+            .downcast_ref::<RecordBuffer>()
+            .ok_or_else(|| RendererError::backend("indirect buffer is not a RecordBuffer"))?;
+
+        self.log.push(RecordCommand::DispatchWorkgroupsIndirect {
+            indirect_buffer: indirect_buffer.id,
+            indirect_offset,
+        });
+
+        Ok(())
+    }
+
+    fn set_render_pipeline(&mut self, render_pipeline: &RenderPipeline) {
+        self.log
+            .push(RecordCommand::SetRenderPipeline(render_pipeline.id()));
+    }
+
+    fn draw(&mut self, vertices: Range<u32>, instances: Range<u32>) {
+        self.log.push(RecordCommand::Draw {
+            vertices,
+            instances,
+        });
+    }
+}
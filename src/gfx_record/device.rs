@@ -0,0 +1,65 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{
+    Buffer, BufferInfo, CommandBuffer, DeviceTrait, RenderPass, RenderPassInfo, RendererError,
+    Texture, TextureInfo,
+};
+
+use super::{
+    RecordBuffer, RecordCommand, RecordCommandBuffer, RecordLog, RecordRenderPass, RecordTexture,
+};
+
+#[derive(Debug, Default)]
+pub struct RecordDevice {
+    pub log: RecordLog,
+    next_id: AtomicUsize,
+}
+
+impl RecordDevice {
+    pub fn new(log: RecordLog) -> Self {
+        RecordDevice {
+            log,
+            next_id: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl DeviceTrait for RecordDevice {
+    fn create_command_buffer(&self) -> CommandBuffer {
+        self.log.push(RecordCommand::CreateCommandBuffer);
+
+        CommandBuffer::new(RecordCommandBuffer::new(self.log.clone()))
+    }
+
+    fn create_texture(&self, desc: &TextureInfo) -> Texture {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.log.push(RecordCommand::CreateTexture {
+            id,
+            desc: desc.clone(),
+        });
+
+        Texture::new(RecordTexture { id }, desc.clone())
+    }
+
+    fn create_buffer(&self, desc: &BufferInfo) -> Buffer {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.log.push(RecordCommand::CreateBuffer {
+            id,
+            desc: desc.clone(),
+        });
+
+        Buffer::new(RecordBuffer { id }, desc.clone())
+    }
+
+    fn create_render_pass(&self, desc: &RenderPassInfo) -> RenderPass {
+        RenderPass::new(RecordRenderPass::new(desc.clone()))
+    }
+
+    fn submit(&self, command_buffers: Vec<CommandBuffer>) -> Result<(), RendererError> {
+        self.log.push(RecordCommand::Submit {
+            command_buffer_count: command_buffers.len(),
+        });
+
+        Ok(())
+    }
+}
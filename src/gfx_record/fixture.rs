@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat, TextureUsages};
+
+use crate::{
+    Device, FrameGraph, ResourceNodeHandle, SwapChain, SwapChainInfo, TextureInfo,
+    TransientResourceCache,
+};
+
+use super::{RecordCommand, RecordDevice, RecordPipelineProvider, RecordSwapChain};
+
+///单层单级的 Rgba8Unorm 渲染附件
+pub fn texture_info(width: u32, height: u32) -> TextureInfo {
+    TextureInfo {
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::Rgba8Unorm,
+        usage: TextureUsages::RENDER_ATTACHMENT,
+        view_formats: vec![],
+    }
+}
+
+///导入名为 swap_chain 的记录交换链
+pub fn import_swap_chain(frame_graph: &mut FrameGraph) -> ResourceNodeHandle<SwapChain> {
+    frame_graph.import(
+        "swap_chain",
+        Arc::new(SwapChain::new(RecordSwapChain {
+            name: "swap_chain".to_string(),
+        })),
+        SwapChainInfo {
+            name: "swap_chain".to_string(),
+        },
+    )
+}
+
+///用记录后端编译并执行，返回记录到的命令
+pub fn run(frame_graph: &mut FrameGraph) -> Vec<RecordCommand> {
+    run_with_cache(frame_graph, &mut TransientResourceCache::default())
+}
+
+///同 run，跨帧复用 transient_resource_cache
+pub fn run_with_cache(
+    frame_graph: &mut FrameGraph,
+    transient_resource_cache: &mut TransientResourceCache,
+) -> Vec<RecordCommand> {
+    let device = RecordDevice::default();
+    let log = device.log.clone();
+    let device = Arc::new(Device::new(device));
+
+    frame_graph.compile(&RecordPipelineProvider).unwrap();
+    frame_graph
+        .execute(&device, transient_resource_cache, &RecordPipelineProvider)
+        .unwrap();
+
+    log.commands()
+}
@@ -0,0 +1,641 @@
+mod buffer;
+mod command_buffer;
+mod device;
+#[cfg(test)]
+pub(crate) mod fixture;
+mod pipeline_provider;
+mod render_pass;
+mod swap_chain;
+mod texture;
+mod texture_view;
+
+pub use buffer::*;
+pub use command_buffer::*;
+pub use device::*;
+pub use pipeline_provider::*;
+pub use render_pass::*;
+pub use swap_chain::*;
+pub use texture::*;
+pub use texture_view::*;
+
+use std::{
+    ops::Range,
+    sync::{Arc, Mutex},
+};
+
+use bevy::render::render_resource::{ComputePipelineId, RenderPipelineId};
+
+use crate::{BufferInfo, TextureInfo};
+
+///记录后端产生的命令
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordCommand {
+    CreateCommandBuffer,
+    CreateTexture {
+        id: usize,
+        desc: TextureInfo,
+    },
+    CreateBuffer {
+        id: usize,
+        desc: BufferInfo,
+    },
+    BeginRenderPass {
+        color_attachments: Vec<String>,
+        resolve_targets: Vec<String>,
+        depth_stencil_attachment: Option<String>,
+    },
+    EndRenderPass,
+    BeginComputePass,
+    EndComputePass,
+    SetRenderPipeline(RenderPipelineId),
+    SetComputePipeline(ComputePipelineId),
+    Draw {
+        vertices: Range<u32>,
+        instances: Range<u32>,
+    },
+    DispatchWorkgroups {
+        x: u32,
+        y: u32,
+        z: u32,
+    },
+    FinishCommandBuffer,
+    DispatchWorkgroupsIndirect {
+        indirect_buffer: usize,
+        indirect_offset: u64,
+    },
+    Submit {
+        command_buffer_count: usize,
+    },
+}
+
+///记录后端共享的命令日志
+#[derive(Debug, Clone, Default)]
+pub struct RecordLog(Arc<Mutex<Vec<RecordCommand>>>);
+
+impl RecordLog {
+    pub fn push(&self, command: RecordCommand) {
+        self.0.lock().unwrap().push(command);
+    }
+
+    pub fn commands(&self) -> Vec<RecordCommand> {
+        self.0.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use bevy::color::LinearRgba;
+    use bevy::render::render_resource::{
+        BufferUsages, CachedRenderPipelineId, TextureFormat, TextureUsages,
+    };
+
+    use crate::{
+        Buffer, BufferInfo, ColorAttachment, ColorAttachmentView, Device, FrameGraph, GpuRead,
+        LoadOp, PassData, PassKind, RenderContext, RenderPass, RenderPassInfo, RendererError,
+        ResourceNodeHandle, ResourceNodeRef, Texture, TextureInfo, TextureViewInfo,
+        TransientResourceCache, WgpuRenderPass,
+    };
+
+    use super::{
+        RecordBuffer, RecordCommand, RecordDevice, RecordPipelineProvider, RecordTexture,
+        fixture::{import_swap_chain, run, run_with_cache, texture_info},
+    };
+
+    struct DrawPass;
+
+    impl PassData for DrawPass {
+        fn execute(&self, render_context: &mut RenderContext) -> Result<(), RendererError> {
+            render_context.draw(0..3, 0..1);
+            Ok(())
+        }
+    }
+
+    struct PipelinePass;
+
+    impl PassData for PipelinePass {
+        fn execute(&self, render_context: &mut RenderContext) -> Result<(), RendererError> {
+            render_context.set_render_pipeline(CachedRenderPipelineId::INVALID)?;
+            render_context.draw(0..3, 0..1);
+            Ok(())
+        }
+    }
+
+    struct ReadPass {
+        texture: ResourceNodeRef<Texture, GpuRead>,
+    }
+
+    impl PassData for ReadPass {
+        fn execute(&self, render_context: &mut RenderContext) -> Result<(), RendererError> {
+            render_context.get_resource(&self.texture)?;
+            render_context.draw(0..3, 0..1);
+            Ok(())
+        }
+    }
+
+    struct DispatchPass {
+        indirect_buffer: ResourceNodeRef<Buffer, GpuRead>,
+    }
+
+    impl PassData for DispatchPass {
+        fn execute(&self, render_context: &mut RenderContext) -> Result<(), RendererError> {
+            render_context.dispatch_workgroups_indirect(&self.indirect_buffer, 16)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_execute() {
+        let mut frame_graph = FrameGraph::default();
+        let swap_chain = import_swap_chain(&mut frame_graph);
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(0, "draw");
+            let swap_chain = builder.write(swap_chain);
+            builder.add_attachment(ColorAttachment::swap_chain(swap_chain));
+            builder.set_pass(DrawPass);
+        }
+
+        let commands = run(&mut frame_graph);
+
+        assert_eq!(
+            commands,
+            [
+                RecordCommand::CreateCommandBuffer,
+                RecordCommand::BeginRenderPass {
+                    color_attachments: vec!["swap_chain".to_string()],
+                    resolve_targets: vec![],
+                    depth_stencil_attachment: None,
+                },
+                RecordCommand::Draw {
+                    vertices: 0..3,
+                    instances: 0..1,
+                },
+                RecordCommand::EndRenderPass,
+                RecordCommand::FinishCommandBuffer,
+                RecordCommand::Submit {
+                    command_buffer_count: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_transient_texture() {
+        let mut transient_resource_cache = TransientResourceCache::default();
+
+        let desc = texture_info(4, 4);
+        let mut commands = vec![];
+
+        for _ in 0..2 {
+            let mut frame_graph = FrameGraph::default();
+            let texture = frame_graph.create("texture", desc.clone());
+
+            //写入和读取分属不同的 DevicePass，纹理要活过第一个通道才能被复用
+            let texture: ResourceNodeHandle<Texture> = {
+                let mut builder = frame_graph.create_pass_node_builder(0, "draw");
+                let written = builder.write(texture);
+                builder.add_attachment(ColorAttachment::texture(
+                    written.clone(),
+                    TextureViewInfo::default(),
+                ));
+                builder.set_pass(DrawPass);
+                written.into()
+            };
+
+            {
+                let mut builder = frame_graph.create_pass_node_builder(1, "read");
+                builder.set_has_side_effect(true);
+                let texture = builder.read(texture);
+                builder.set_pass(ReadPass { texture });
+            }
+
+            commands.extend(run_with_cache(
+                &mut frame_graph,
+                &mut transient_resource_cache,
+            ));
+        }
+
+        let created: Vec<_> = commands
+            .into_iter()
+            .filter(|command| matches!(command, RecordCommand::CreateTexture { .. }))
+            .collect();
+
+        assert_eq!(created, [RecordCommand::CreateTexture { id: 0, desc }]);
+    }
+
+    #[test]
+    fn test_transient_texture_across_device_passes() {
+        let mut transient_resource_cache = TransientResourceCache::default();
+
+        let mut frame_graph = FrameGraph::default();
+        let swap_chain = import_swap_chain(&mut frame_graph);
+        let gbuffer = frame_graph.create("gbuffer", texture_info(4, 4));
+
+        let gbuffer: ResourceNodeHandle<Texture> = {
+            let mut builder = frame_graph.create_pass_node_builder(0, "gbuffer");
+            let written = builder.write(gbuffer);
+            builder.add_attachment(ColorAttachment::texture(
+                written.clone(),
+                TextureViewInfo::default(),
+            ));
+            builder.set_pass(DrawPass);
+            written.into()
+        };
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(1, "lighting");
+            let texture = builder.read(gbuffer);
+            let swap_chain = builder.write(swap_chain);
+            builder.add_attachment(ColorAttachment::swap_chain(swap_chain));
+            builder.set_pass(ReadPass { texture });
+        }
+
+        let commands = run_with_cache(&mut frame_graph, &mut transient_resource_cache);
+
+        assert_eq!(
+            commands
+                .iter()
+                .filter(|command| matches!(command, RecordCommand::BeginRenderPass { .. }))
+                .count(),
+            2
+        );
+        //最后读取它的节点执行完后归还缓存
+        assert_eq!(transient_resource_cache.len(), 1);
+    }
+
+    #[test]
+    fn test_alias_transient_textures() {
+        let mut frame_graph = FrameGraph::default();
+        let a = frame_graph.create("a", texture_info(4, 4));
+        let b = frame_graph.create(
+            "b",
+            TextureInfo {
+                usage: TextureUsages::STORAGE_BINDING,
+                ..texture_info(4, 4)
+            },
+        );
+
+        //a 与 b 的生命周期不重叠，b 复用 a 归还的纹理
+        for (insert_point, texture) in [a, b].into_iter().enumerate() {
+            let written: ResourceNodeHandle<Texture> = {
+                let mut builder = frame_graph
+                    .create_pass_node_builder(insert_point * 2, &format!("write_{insert_point}"));
+                let written = builder.write(texture);
+                builder.set_pass(DrawPass);
+                written.into()
+            };
+
+            let mut builder = frame_graph
+                .create_pass_node_builder(insert_point * 2 + 1, &format!("read_{insert_point}"));
+            builder.set_has_side_effect(true);
+            let texture = builder.read(written);
+            builder.set_pass(ReadPass { texture });
+        }
+
+        let commands = run(&mut frame_graph);
+
+        assert_eq!(frame_graph.transient_memory_report().slot_count, 1);
+        assert_eq!(
+            commands
+                .iter()
+                .filter(|command| matches!(command, RecordCommand::CreateTexture { .. }))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_texture_attachment() {
+        let mut desc = texture_info(4, 4);
+        desc.size.depth_or_array_layers = 2;
+        desc.mip_level_count = 2;
+
+        let mut frame_graph = FrameGraph::default();
+        let imported = frame_graph.import(
+            "imported",
+            Arc::new(Texture::new(RecordTexture { id: 9 }, desc.clone())),
+            desc,
+        );
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(0, "draw");
+            builder.set_has_side_effect(true);
+            let imported = builder.write(imported);
+            builder.add_attachment(ColorAttachment::texture(
+                imported,
+                TextureViewInfo::single(1, 1),
+            ));
+            builder.set_pass(DrawPass);
+        }
+
+        let commands = run(&mut frame_graph);
+
+        assert!(commands.contains(&RecordCommand::BeginRenderPass {
+            color_attachments: vec!["texture_9_mip1_layer1".to_string()],
+            resolve_targets: vec![],
+            depth_stencil_attachment: None,
+        }));
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        let mut frame_graph = FrameGraph::default();
+        let swap_chain = import_swap_chain(&mut frame_graph);
+        let multisampled = frame_graph.create(
+            "multisampled",
+            TextureInfo {
+                sample_count: 4,
+                format: TextureFormat::Bgra8UnormSrgb,
+                ..texture_info(4, 4)
+            },
+        );
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(0, "draw");
+            let multisampled = builder.write(multisampled);
+            let swap_chain = builder.write(swap_chain);
+            builder.add_attachment(
+                ColorAttachment::texture(multisampled, TextureViewInfo::default())
+                    .with_resolve_target(ColorAttachmentView::SwapChain(swap_chain)),
+            );
+            builder.set_pass(DrawPass);
+        }
+
+        let commands = run(&mut frame_graph);
+
+        assert!(commands.contains(&RecordCommand::BeginRenderPass {
+            color_attachments: vec!["texture_0".to_string()],
+            resolve_targets: vec!["swap_chain".to_string()],
+            depth_stencil_attachment: None,
+        }));
+    }
+
+    #[test]
+    fn test_merge_render_passes() {
+        let mut frame_graph = FrameGraph::default();
+        let mut swap_chain = import_swap_chain(&mut frame_graph);
+
+        //第二个节点加载上一个节点的结果，可以合并；第三个节点重新清除，不能合并
+        for (insert_point, load) in [
+            LoadOp::Clear(LinearRgba::BLACK),
+            LoadOp::Load,
+            LoadOp::Clear(LinearRgba::BLACK),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let mut builder =
+                frame_graph.create_pass_node_builder(insert_point, &format!("draw_{insert_point}"));
+            let written = builder.write(swap_chain);
+            builder.add_attachment(ColorAttachment::swap_chain(written.clone()).with_load_op(load));
+            builder.set_pass(DrawPass);
+            swap_chain = written.into();
+        }
+
+        let commands = run(&mut frame_graph);
+        let count = |expected: fn(&RecordCommand) -> bool| {
+            commands.iter().filter(|command| expected(command)).count()
+        };
+
+        assert_eq!(
+            count(|command| matches!(command, RecordCommand::BeginRenderPass { .. })),
+            2
+        );
+        assert_eq!(
+            count(|command| matches!(command, RecordCommand::Draw { .. })),
+            3
+        );
+    }
+
+    #[test]
+    fn test_merge_transient_target() {
+        let mut frame_graph = FrameGraph::default();
+        let target = frame_graph.create("target", texture_info(4, 4));
+
+        let cleared: ResourceNodeHandle<Texture> = {
+            let mut builder = frame_graph.create_pass_node_builder(0, "clear");
+            let written = builder.write(target);
+            builder.add_attachment(ColorAttachment::texture(
+                written.clone(),
+                TextureViewInfo::default(),
+            ));
+            builder.set_pass(DrawPass);
+            written.into()
+        };
+
+        //读取被加载的上一版本不妨碍合并
+        let loaded: ResourceNodeHandle<Texture> = {
+            let mut builder = frame_graph.create_pass_node_builder(1, "load");
+            builder.read(cleared.clone());
+            let written = builder.write(cleared);
+            builder.add_attachment(
+                ColorAttachment::texture(written.clone(), TextureViewInfo::default())
+                    .with_load_op(LoadOp::Load),
+            );
+            builder.set_pass(DrawPass);
+            written.into()
+        };
+        frame_graph.keep(&loaded);
+
+        let commands = run(&mut frame_graph);
+        let count = |expected: fn(&RecordCommand) -> bool| {
+            commands.iter().filter(|command| expected(command)).count()
+        };
+
+        assert_eq!(
+            count(|command| matches!(command, RecordCommand::BeginRenderPass { .. })),
+            1
+        );
+        assert_eq!(
+            count(|command| matches!(command, RecordCommand::Draw { .. })),
+            2
+        );
+    }
+
+    #[test]
+    fn test_single_command_buffer() {
+        let desc = BufferInfo {
+            size: 32,
+            usage: BufferUsages::INDIRECT,
+            mapped_at_creation: false,
+        };
+
+        let mut frame_graph = FrameGraph::default();
+        let indirect_buffer = frame_graph.import(
+            "indirect_buffer",
+            Arc::new(Buffer::new(RecordBuffer { id: 7 }, desc.clone())),
+            desc,
+        );
+        let swap_chain = import_swap_chain(&mut frame_graph);
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(0, "dispatch");
+            builder.set_kind(PassKind::Compute);
+            builder.set_has_side_effect(true);
+            let indirect_buffer = builder.read(indirect_buffer);
+            builder.set_pass(DispatchPass { indirect_buffer });
+        }
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(1, "draw");
+            let swap_chain = builder.write(swap_chain);
+            builder.add_attachment(ColorAttachment::swap_chain(swap_chain));
+            builder.set_pass(DrawPass);
+        }
+
+        let commands = run(&mut frame_graph);
+
+        assert_eq!(commands.first(), Some(&RecordCommand::CreateCommandBuffer));
+        assert_eq!(
+            commands
+                .iter()
+                .filter(|command| matches!(command, RecordCommand::CreateCommandBuffer))
+                .count(),
+            1
+        );
+        assert_eq!(
+            commands.last(),
+            Some(&RecordCommand::Submit {
+                command_buffer_count: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_pass_failure() {
+        let device = RecordDevice::default();
+        let log = device.log.clone();
+        let device = Arc::new(Device::new(device));
+
+        let mut frame_graph = FrameGraph::default();
+        let swap_chain = import_swap_chain(&mut frame_graph);
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(0, "pipeline");
+            let swap_chain = builder.write(swap_chain);
+            builder.add_attachment(ColorAttachment::swap_chain(swap_chain));
+            builder.set_pass(PipelinePass);
+        }
+
+        frame_graph.compile(&RecordPipelineProvider).unwrap();
+        let Err(RendererError::PassesFailed { failures }) = frame_graph.execute(
+            &device,
+            &mut TransientResourceCache::default(),
+            &RecordPipelineProvider,
+        ) else {
+            panic!("expected a failed pass");
+        };
+
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "pipeline");
+        assert!(matches!(
+            failures[0].1,
+            RendererError::RenderPipelineNotReady(CachedRenderPipelineId::INVALID)
+        ));
+
+        //失败的节点不影响提交
+        assert_eq!(
+            log.commands().last(),
+            Some(&RecordCommand::Submit {
+                command_buffer_count: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_compile_cache() {
+        let mut transient_resource_cache = TransientResourceCache::default();
+
+        let setup = |frame_graph: &mut FrameGraph, pass_count: usize| {
+            let mut swap_chain = import_swap_chain(frame_graph);
+
+            for insert_point in 0..pass_count {
+                let mut builder = frame_graph
+                    .create_pass_node_builder(insert_point, &format!("draw_{insert_point}"));
+                let written = builder.write(swap_chain);
+                builder.add_attachment(
+                    ColorAttachment::swap_chain(written.clone()).with_load_op(LoadOp::Load),
+                );
+                builder.set_pass(DrawPass);
+                swap_chain = written.into();
+            }
+        };
+
+        let mut frame_graph = FrameGraph::default();
+        let mut frames = vec![];
+
+        for pass_count in [2, 2, 3] {
+            setup(&mut frame_graph, pass_count);
+            let commands = run_with_cache(&mut frame_graph, &mut transient_resource_cache);
+
+            frames.push((frame_graph.is_compile_cache_hit(), commands));
+        }
+
+        assert!(!frames[0].0);
+        assert!(frames[1].0);
+        assert!(!frames[2].0);
+        assert_eq!(frames[0].1, frames[1].1);
+        assert_ne!(frames[1].1, frames[2].1);
+    }
+
+    #[test]
+    fn test_compute_pass() {
+        let desc = BufferInfo {
+            size: 32,
+            usage: BufferUsages::INDIRECT,
+            mapped_at_creation: false,
+        };
+
+        let mut frame_graph = FrameGraph::default();
+        let indirect_buffer = frame_graph.import(
+            "indirect_buffer",
+            Arc::new(Buffer::new(RecordBuffer { id: 7 }, desc.clone())),
+            desc,
+        );
+
+        {
+            let mut builder = frame_graph.create_pass_node_builder(0, "dispatch");
+            builder.set_kind(PassKind::Compute);
+            builder.set_has_side_effect(true);
+            let indirect_buffer = builder.read(indirect_buffer);
+            builder.set_pass(DispatchPass { indirect_buffer });
+        }
+
+        let commands = run(&mut frame_graph);
+
+        assert_eq!(
+            commands,
+            [
+                RecordCommand::CreateCommandBuffer,
+                RecordCommand::BeginComputePass,
+                RecordCommand::DispatchWorkgroupsIndirect {
+                    indirect_buffer: 7,
+                    indirect_offset: 16,
+                },
+                RecordCommand::EndComputePass,
+                RecordCommand::FinishCommandBuffer,
+                RecordCommand::Submit {
+                    command_buffer_count: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_backend_error() {
+        let device = Device::new(RecordDevice::default());
+        let mut command_buffer = device.create_command_buffer();
+        let render_pass = RenderPass::new(WgpuRenderPass::new(RenderPassInfo::default()));
+
+        assert!(matches!(
+            command_buffer.begin_render_pass(&device, render_pass),
+            Err(RendererError::Backend { .. })
+        ));
+    }
+}
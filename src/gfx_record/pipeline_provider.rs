@@ -0,0 +1,19 @@
+use bevy::render::render_resource::{
+    CachedComputePipelineId, CachedRenderPipelineId, ComputePipeline, RenderPipeline,
+};
+
+use crate::PipelineProvider;
+
+///记录后端没有真实的管线，所有管线都视为未就绪
+#[derive(Debug, Default)]
+pub struct RecordPipelineProvider;
+
+impl PipelineProvider for RecordPipelineProvider {
+    fn get_render_pipeline(&self, _id: CachedRenderPipelineId) -> Option<&RenderPipeline> {
+        None
+    }
+
+    fn get_compute_pipeline(&self, _id: CachedComputePipelineId) -> Option<&ComputePipeline> {
+        None
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{
+    frame_graph::RenderContext,
+    gfx_base::{RenderPassInfo, RenderPassTrait, TextureView},
+};
+
+#[derive(Debug)]
+pub struct RecordRenderPass {
+    desc: RenderPassInfo,
+    ///与颜色附件一一对应，资源缺失时为None
+    pub texture_views: Option<Vec<Option<TextureView>>>,
+    pub resolve_target_views: Option<Vec<Option<TextureView>>>,
+    pub depth_stencil_view: Option<TextureView>,
+}
+
+impl RecordRenderPass {
+    pub fn new(desc: RenderPassInfo) -> Self {
+        RecordRenderPass {
+            desc,
+            texture_views: None,
+            resolve_target_views: None,
+            depth_stencil_view: None,
+        }
+    }
+}
+
+impl RenderPassTrait for RecordRenderPass {
+    fn do_init(&mut self, render_context: &RenderContext) {
+        let mut texture_views = vec![];
+        let mut resolve_target_views = vec![];
+
+        for color_attachment in self.desc.color_attachments.iter() {
+            texture_views.push(color_attachment.view.get_texture_view(render_context));
+            resolve_target_views.push(
+                color_attachment
+                    .resolve_target
+                    .as_ref()
+                    .and_then(|resolve_target| resolve_target.get_texture_view(render_context)),
+            );
+        }
+
+        self.texture_views = Some(texture_views);
+        self.resolve_target_views = Some(resolve_target_views);
+
+        self.depth_stencil_view =
+            self.desc
+                .depth_stencil_attachment
+                .as_ref()
+                .and_then(|depth_stencil_attachment| {
+                    depth_stencil_attachment
+                        .texture
+                        .get_texture_view(render_context)
+                });
+    }
+}
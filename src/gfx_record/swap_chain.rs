@@ -0,0 +1,14 @@
+use crate::{SwapChainTrait, TextureView};
+
+use super::RecordTextureView;
+
+#[derive(Debug)]
+pub struct RecordSwapChain {
+    pub name: String,
+}
+
+impl SwapChainTrait for RecordSwapChain {
+    fn get_texture_view(&self) -> TextureView {
+        TextureView::new(RecordTextureView(self.name.clone()))
+    }
+}
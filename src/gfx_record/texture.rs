@@ -0,0 +1,21 @@
+use crate::{TextureTrait, TextureView, TextureViewInfo};
+
+use super::RecordTextureView;
+
+#[derive(Debug)]
+pub struct RecordTexture {
+    pub id: usize,
+}
+
+impl TextureTrait for RecordTexture {
+    fn get_texture_view(&self, info: &TextureViewInfo) -> TextureView {
+        if *info == TextureViewInfo::default() {
+            return TextureView::new(RecordTextureView(format!("texture_{}", self.id)));
+        }
+
+        TextureView::new(RecordTextureView(format!(
+            "texture_{}_mip{}_layer{}",
+            self.id, info.base_mip_level, info.base_array_layer
+        )))
+    }
+}
@@ -0,0 +1,6 @@
+use crate::TextureViewTrait;
+
+#[derive(Debug)]
+pub struct RecordTextureView(pub String);
+
+impl TextureViewTrait for RecordTextureView {}
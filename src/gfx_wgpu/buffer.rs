@@ -0,0 +1,8 @@
+use crate::BufferTrait;
+
+#[derive(Debug)]
+pub struct WgpuBuffer {
+    pub buffer: wgpu::Buffer,
+}
+
+impl BufferTrait for WgpuBuffer {}
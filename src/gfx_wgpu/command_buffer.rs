@@ -1,66 +1,178 @@
 use std::ops::Range;
 
-use bevy::render::render_resource::RenderPipeline;
+use bevy::render::render_resource::{ComputePipeline, RenderPipeline};
 
 use crate::{
-    gfx_base::{CommandBufferTrait, Device, RenderPass},
-    gfx_wgpu::{WgpuDevice, WgpuRenderPass, WgpuTextureView},
+    RendererError,
+    gfx_base::TextureView,
+    gfx_base::{Buffer, CommandBufferTrait, Device, RenderPass},
+    gfx_wgpu::{WgpuBuffer, WgpuDevice, WgpuRenderPass, WgpuTextureView},
 };
 
+fn wgpu_texture_view(texture_view: &TextureView) -> Result<&WgpuTextureView, RendererError> {
+    texture_view
+        .downcast_ref::<WgpuTextureView>()
+        .ok_or_else(|| RendererError::backend("texture view is not a WgpuTextureView"))
+}
+
+fn wgpu_device(device: &Device) -> Result<&WgpuDevice, RendererError> {
+    device
+        .downcast_ref::<WgpuDevice>()
+        .ok_or_else(|| RendererError::backend("device is not a WgpuDevice"))
+}
+
+///一个 WgpuCommandBuffer 只持有一个 encoder，多个通道依次录制到其中
 #[derive(Debug, Default)]
 pub struct WgpuCommandBuffer {
     encoder: Option<wgpu::CommandEncoder>,
     render_pass: Option<wgpu::RenderPass<'static>>,
+    compute_pass: Option<wgpu::ComputePass<'static>>,
     pub command_buffer: Option<wgpu::CommandBuffer>,
 }
 
 impl CommandBufferTrait for WgpuCommandBuffer {
-    fn begin_render_pass(&mut self, device: &Device, render_pass: RenderPass) {
-        let device = device.downcast_ref::<WgpuDevice>().unwrap();
+    fn begin_render_pass(
+        &mut self,
+        device: &Device,
+        render_pass: RenderPass,
+    ) -> Result<(), RendererError> {
+        let device = wgpu_device(device)?;
 
-        let mut render_pass = render_pass.downcast::<WgpuRenderPass>().unwrap();
+        let mut render_pass = render_pass
+            .downcast::<WgpuRenderPass>()
+            .ok_or_else(|| RendererError::backend("render pass is not a WgpuRenderPass"))?;
 
         let mut color_attachments = vec![];
 
-        let texture_views = render_pass.texture_views.take().unwrap();
-
-        for texture_view in texture_views.iter() {
-            let texture_view = texture_view.downcast_ref::<WgpuTextureView>().unwrap();
+        let texture_views = render_pass
+            .texture_views
+            .take()
+            .ok_or_else(|| RendererError::backend("render pass has no texture views"))?;
+        let resolve_target_views = render_pass
+            .resolve_target_views
+            .take()
+            .ok_or_else(|| RendererError::backend("render pass has no resolve target views"))?;
+
+        for ((color_attachment, texture_view), resolve_target_view) in render_pass
+            .desc()
+            .color_attachments
+            .iter()
+            .zip(texture_views.iter())
+            .zip(resolve_target_views.iter())
+        {
+            let Some(texture_view) = texture_view else {
+                color_attachments.push(None);
+                continue;
+            };
+
+            let texture_view = wgpu_texture_view(texture_view)?;
+            let resolve_target = match resolve_target_view {
+                Some(resolve_target_view) => Some(&wgpu_texture_view(resolve_target_view)?.0),
+                None => None,
+            };
 
             color_attachments.push(Some(wgpu::RenderPassColorAttachment {
                 view: &texture_view.0,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::GREEN),
-                    store: wgpu::StoreOp::Store,
-                },
+                resolve_target,
+                ops: color_attachment.ops.into(),
             }));
         }
 
-        let mut encoder = device.device.create_command_encoder(&Default::default());
+        let depth_stencil_view = render_pass.depth_stencil_view.take();
+        let depth_stencil_attachment = match render_pass
+            .desc()
+            .depth_stencil_attachment
+            .as_ref()
+            .zip(depth_stencil_view.as_ref())
+        {
+            Some((depth_stencil_attachment, texture_view)) => {
+                let texture_view = wgpu_texture_view(texture_view)?;
+
+                Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &texture_view.0,
+                    depth_ops: depth_stencil_attachment.depth_ops.map(Into::into),
+                    stencil_ops: depth_stencil_attachment.stencil_ops.map(Into::into),
+                })
+            }
+            None => None,
+        };
+
+        let encoder = self
+            .encoder
+            .get_or_insert_with(|| device.device.create_command_encoder(&Default::default()));
         let render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: None,
             color_attachments: &color_attachments,
-            depth_stencil_attachment: None,
+            depth_stencil_attachment,
             timestamp_writes: None,
             occlusion_query_set: None,
         });
 
         let render_pass = render_pass.forget_lifetime();
 
-        self.encoder = Some(encoder);
         self.render_pass = Some(render_pass);
+
+        Ok(())
     }
 
     fn end_render_pass(&mut self) {
-        let render_pass = self.render_pass.take().unwrap();
-        let encoder = self.encoder.take().unwrap();
+        drop(self.render_pass.take());
+    }
 
-        drop(render_pass);
+    fn begin_compute_pass(&mut self, device: &Device) -> Result<(), RendererError> {
+        let device = wgpu_device(device)?;
 
-        let command_buffer = encoder.finish();
+        let encoder = self
+            .encoder
+            .get_or_insert_with(|| device.device.create_command_encoder(&Default::default()));
+        let compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: None,
+            timestamp_writes: None,
+        });
+
+        let compute_pass = compute_pass.forget_lifetime();
+
+        self.compute_pass = Some(compute_pass);
+
+        Ok(())
+    }
+
+    fn end_compute_pass(&mut self) {
+        drop(self.compute_pass.take());
+    }
 
-        self.command_buffer = Some(command_buffer);
+    fn finish(&mut self) {
+        if let Some(encoder) = self.encoder.take() {
+            self.command_buffer = Some(encoder.finish());
+        }
+    }
+
+    fn set_compute_pipeline(&mut self, compute_pipeline: &ComputePipeline) {
+        if let Some(compute_pass) = self.compute_pass.as_mut() {
+            compute_pass.set_pipeline(compute_pipeline);
+        }
+    }
+
+    fn dispatch_workgroups(&mut self, x: u32, y: u32, z: u32) {
+        if let Some(compute_pass) = self.compute_pass.as_mut() {
+            compute_pass.dispatch_workgroups(x, y, z);
+        }
+    }
+
+    fn dispatch_workgroups_indirect(
+        &mut self,
+        indirect_buffer: &Buffer,
+        indirect_offset: u64,
+    ) -> Result<(), RendererError> {
+        let indirect_buffer = indirect_buffer
+            .downcast_ref::<WgpuBuffer>()
+            .ok_or_else(|| RendererError::backend("indirect buffer is not a WgpuBuffer"))?;
+
+        if let Some(compute_pass) = self.compute_pass.as_mut() {
+            compute_pass.dispatch_workgroups_indirect(&indirect_buffer.buffer, indirect_offset);
+        }
+
+        Ok(())
     }
 
     fn set_render_pipeline(&mut self, render_pipeline: &RenderPipeline) {
@@ -83,3 +195,21 @@ impl CommandBufferTrait for WgpuCommandBuffer {
     //     }
     // }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{CommandBuffer, Device, RecordDevice, RendererError};
+
+    use super::WgpuCommandBuffer;
+
+    #[test]
+    fn test_backend_error() {
+        let device = Device::new(RecordDevice::default());
+        let mut command_buffer = CommandBuffer::new(WgpuCommandBuffer::default());
+
+        assert!(matches!(
+            command_buffer.begin_compute_pass(&device),
+            Err(RendererError::Backend { .. })
+        ));
+    }
+}
@@ -2,9 +2,12 @@ use std::fmt::Debug;
 
 use bevy::render::renderer::{RenderDevice, RenderQueue};
 
-use crate::{CommandBuffer, DeviceTrait, RenderPass, RenderPassInfo};
+use crate::{
+    Buffer, BufferInfo, CommandBuffer, DeviceTrait, RenderPass, RenderPassInfo, RendererError,
+    Texture, TextureInfo,
+};
 
-use super::{WgpuCommandBuffer, WgpuRenderPass};
+use super::{WgpuBuffer, WgpuCommandBuffer, WgpuRenderPass, WgpuTexture};
 
 pub struct WgpuDevice {
     pub device: RenderDevice,
@@ -24,15 +27,52 @@ impl DeviceTrait for WgpuDevice {
         CommandBuffer::new(WgpuCommandBuffer::default())
     }
 
+    fn create_texture(&self, desc: &TextureInfo) -> Texture {
+        let texture = self
+            .device
+            .wgpu_device()
+            .create_texture(&wgpu::TextureDescriptor {
+                label: None,
+                size: desc.size,
+                mip_level_count: desc.mip_level_count,
+                sample_count: desc.sample_count,
+                dimension: desc.dimension,
+                format: desc.format,
+                usage: desc.usage,
+                view_formats: &desc.view_formats,
+            });
+
+        Texture::new(WgpuTexture { texture }, desc.clone())
+    }
+
+    fn create_buffer(&self, desc: &BufferInfo) -> Buffer {
+        let buffer = self
+            .device
+            .wgpu_device()
+            .create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: desc.size,
+                usage: desc.usage,
+                mapped_at_creation: desc.mapped_at_creation,
+            });
+
+        Buffer::new(WgpuBuffer { buffer }, desc.clone())
+    }
+
     fn create_render_pass(&self, desc: &RenderPassInfo) -> crate::RenderPass {
         RenderPass::new(WgpuRenderPass::new(desc.clone()))
     }
 
-    fn submit(&self, command_buffers: Vec<crate::CommandBuffer>) {
+    fn submit(&self, command_buffers: Vec<crate::CommandBuffer>) -> Result<(), RendererError> {
         let mut targets = vec![];
 
         for command_buffer in command_buffers.into_iter() {
-            let mut command_buffer = command_buffer.downcast::<WgpuCommandBuffer>().unwrap();
+            let mut command_buffer =
+                command_buffer
+                    .downcast::<WgpuCommandBuffer>()
+                    .ok_or_else(|| {
+                        RendererError::backend("command buffer is not a WgpuCommandBuffer")
+                    })?;
 
             if let Some(command_buffer) = command_buffer.command_buffer.take() {
                 targets.push(command_buffer);
@@ -40,5 +80,7 @@ impl DeviceTrait for WgpuDevice {
         }
 
         self.queue.submit(targets);
+
+        Ok(())
     }
 }
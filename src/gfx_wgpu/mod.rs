@@ -1,11 +1,16 @@
+pub mod buffer;
 pub mod command_buffer;
 pub mod device;
+pub mod operations;
 pub mod render_pass;
 pub mod swap_chain;
+pub mod texture;
 pub mod texture_view;
 
+pub use buffer::*;
 pub use command_buffer::*;
 pub use device::*;
 pub use render_pass::*;
 pub use swap_chain::*;
+pub use texture::*;
 pub use texture_view::*;
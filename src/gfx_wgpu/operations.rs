@@ -0,0 +1,30 @@
+use crate::{LoadOp, Operations, StoreOp};
+
+impl<V: Into<U>, U: Default> From<LoadOp<V>> for wgpu::LoadOp<U> {
+    fn from(value: LoadOp<V>) -> Self {
+        match value {
+            LoadOp::Clear(value) => wgpu::LoadOp::Clear(value.into()),
+            LoadOp::Load => wgpu::LoadOp::Load,
+            //wgpu 没有 DontCare，初始内容无关时 clear 的开销最低
+            LoadOp::DontCare => wgpu::LoadOp::Clear(U::default()),
+        }
+    }
+}
+
+impl From<StoreOp> for wgpu::StoreOp {
+    fn from(value: StoreOp) -> Self {
+        match value {
+            StoreOp::Store => wgpu::StoreOp::Store,
+            StoreOp::Discard => wgpu::StoreOp::Discard,
+        }
+    }
+}
+
+impl<V: Into<U>, U: Default> From<Operations<V>> for wgpu::Operations<U> {
+    fn from(value: Operations<V>) -> Self {
+        wgpu::Operations {
+            load: value.load.into(),
+            store: value.store.into(),
+        }
+    }
+}
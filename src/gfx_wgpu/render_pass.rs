@@ -1,5 +1,4 @@
 use crate::{
-    ColorAttachment, SwapChain,
     frame_graph::RenderContext,
     gfx_base::{RenderPassInfo, RenderPassTrait, TextureView},
 };
@@ -7,7 +6,10 @@ use crate::{
 #[derive(Debug)]
 pub struct WgpuRenderPass {
     desc: RenderPassInfo,
-    pub texture_views: Option<Vec<TextureView>>,
+    ///与颜色附件一一对应，资源缺失时为None
+    pub texture_views: Option<Vec<Option<TextureView>>>,
+    pub resolve_target_views: Option<Vec<Option<TextureView>>>,
+    pub depth_stencil_view: Option<TextureView>,
 }
 
 impl WgpuRenderPass {
@@ -15,24 +17,42 @@ impl WgpuRenderPass {
         WgpuRenderPass {
             desc,
             texture_views: None,
+            resolve_target_views: None,
+            depth_stencil_view: None,
         }
     }
+
+    pub fn desc(&self) -> &RenderPassInfo {
+        &self.desc
+    }
 }
 
 impl RenderPassTrait for WgpuRenderPass {
     fn do_init(&mut self, render_context: &RenderContext) {
         let mut texture_views = vec![];
+        let mut resolve_target_views = vec![];
 
         for color_attachment in self.desc.color_attachments.iter() {
-            match color_attachment {
-                ColorAttachment::SwapChain(handle) => {
-                    if let Some(resource) = render_context.get_resource::<SwapChain>(handle) {
-                        texture_views.push(resource.get_texture_view());
-                    }
-                }
-            }
+            texture_views.push(color_attachment.view.get_texture_view(render_context));
+            resolve_target_views.push(
+                color_attachment
+                    .resolve_target
+                    .as_ref()
+                    .and_then(|resolve_target| resolve_target.get_texture_view(render_context)),
+            );
         }
 
         self.texture_views = Some(texture_views);
+        self.resolve_target_views = Some(resolve_target_views);
+
+        self.depth_stencil_view =
+            self.desc
+                .depth_stencil_attachment
+                .as_ref()
+                .and_then(|depth_stencil_attachment| {
+                    depth_stencil_attachment
+                        .texture
+                        .get_texture_view(render_context)
+                });
     }
 }
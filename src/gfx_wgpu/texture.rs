@@ -0,0 +1,26 @@
+use crate::{TextureTrait, TextureView, TextureViewInfo};
+
+use super::WgpuTextureView;
+
+#[derive(Debug)]
+pub struct WgpuTexture {
+    pub texture: wgpu::Texture,
+}
+
+impl TextureTrait for WgpuTexture {
+    fn get_texture_view(&self, info: &TextureViewInfo) -> TextureView {
+        TextureView::new(WgpuTextureView(self.texture.create_view(
+            &wgpu::TextureViewDescriptor {
+                label: None,
+                format: info.format,
+                dimension: info.dimension,
+                usage: None,
+                aspect: info.aspect,
+                base_mip_level: info.base_mip_level,
+                mip_level_count: info.mip_level_count,
+                base_array_layer: info.base_array_layer,
+                array_layer_count: info.array_layer_count,
+            },
+        )))
+    }
+}
@@ -1,33 +1,44 @@
+mod app;
 mod error;
 mod frame_graph;
 mod gfx_base;
+mod gfx_record;
 mod gfx_wgpu;
+mod setup_order;
 mod setup_pass;
 mod setup_resource;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+pub use app::*;
 pub use error::*;
 pub use frame_graph::*;
 pub use gfx_base::*;
+pub use gfx_record::*;
 pub use gfx_wgpu::*;
+pub use setup_order::*;
 pub use setup_pass::*;
 pub use setup_resource::*;
 
 use bevy::{
-    app::{App, Plugin},
+    app::{App, First, Plugin},
     ecs::{
-        entity::Entity,
-        query::{With, Without},
+        component::Component,
+        entity::{ContainsEntity, Entity, EntityHashMap, EntityHashSet},
+        query::{Has, With, Without},
         resource::Resource,
         schedule::{IntoScheduleConfigs, SystemSet},
-        system::{Commands, Query, Res, ResMut},
+        system::{Commands, Local, Query, Res, ResMut},
     },
+    log::{debug, error},
     render::{
         Render, RenderApp, RenderSet,
-        render_resource::PipelineCache,
+        camera::{ExtractedCamera, NormalizedRenderTarget},
+        extract_component::ExtractComponentPlugin,
+        render_resource::{PipelineCache, SurfaceTexture},
         renderer::{RenderDevice as BevyRenderDevice, RenderQueue},
-        view::ViewTarget,
+        sync_world::MainEntity,
+        view::{ExtractedWindows, ViewTarget},
     },
 };
 
@@ -39,6 +50,8 @@ pub enum FrameGraphSet {
     SetupPassData,
     Compile,
     Execute,
+    ///在 bevy 的 render_system 之后呈现 FrameGraph 渲染的窗口
+    Present,
 }
 
 #[derive(Debug, Resource)]
@@ -46,14 +59,50 @@ pub struct ProtoRenderDevice {
     pub device: Arc<Device>,
 }
 
+///本帧编译和执行 FrameGraph 时产生的错误，每帧编译前清空
+///渲染世界中的实体是视图实体，主世界中的是对应的相机实体，主世界看到的是上一帧渲染的结果
+#[derive(Debug, Default, Resource)]
+pub struct FrameGraphErrors {
+    pub errors: Vec<(Entity, RendererError)>,
+}
+
+///渲染世界执行完成后把错误交给主世界
+#[derive(Debug, Default, Clone, Resource)]
+pub struct SharedFrameGraphErrors(Arc<Mutex<Vec<(Entity, RendererError)>>>);
+
+///FrameGraph 渲染到的窗口，多个相机渲染到同一窗口时只呈现一次
+#[derive(Default, Resource)]
+pub struct PresentWindows {
+    windows: EntityHashSet,
+    surface_textures: Vec<SurfaceTexture>,
+}
+
+impl PresentWindows {
+    pub fn windows(&self) -> &EntityHashSet {
+        &self.windows
+    }
+}
+
+///FrameGraph 所属视图的渲染目标，目标改变时重建 FrameGraph
+#[derive(Debug, Default, Component)]
+pub struct FrameGraphTarget(pub Option<NormalizedRenderTarget>);
+
 pub struct ProtoRenderPlugin;
 
 impl Plugin for ProtoRenderPlugin {
-    fn build(&self, _app: &mut App) {
-        // app.add_plugins(ImportedPassResourcePlugin::<ExtractedWindows>::default());
+    fn build(&self, app: &mut App) {
+        app.add_plugins(ExtractComponentPlugin::<FrameGraphPasses>::default());
     }
 
     fn finish(&self, app: &mut App) {
+        let shared_errors = SharedFrameGraphErrors::default();
+
+        if app.get_sub_app(RenderApp).is_some() {
+            app.init_resource::<FrameGraphErrors>()
+                .insert_resource(shared_errors.clone())
+                .add_systems(First, receive_frame_graph_errors);
+        }
+
         if let Some(render_app) = app.get_sub_app_mut(RenderApp) {
             render_app.configure_sets(
                 Render,
@@ -69,6 +118,12 @@ impl Plugin for ProtoRenderPlugin {
                     .after(RenderSet::PrepareBindGroups)
                     .before(RenderSet::Render),
             );
+            render_app.configure_sets(
+                Render,
+                FrameGraphSet::Present
+                    .after(RenderSet::Render)
+                    .before(RenderSet::Cleanup),
+            );
 
             let bevy_render_device = render_app.world().resource::<BevyRenderDevice>().clone();
             let queue = render_app.world().resource::<RenderQueue>().clone();
@@ -77,18 +132,26 @@ impl Plugin for ProtoRenderPlugin {
                 queue,
             });
 
-            let mut setup_resources = SetupResources::default();
-
-            setup_resources.add_node(SwapChainSetupResourceNode);
-
             render_app.insert_resource(ProtoRenderDevice {
                 device: Arc::new(render_device),
             });
             render_app.insert_resource(TransientResourceCache::default());
+            render_app.insert_resource(FrameGraphErrors::default());
+            render_app.insert_resource(shared_errors);
+            render_app.init_resource::<PresentWindows>();
             render_app.insert_resource(SetupPassesFrameGraph::default());
-            render_app.insert_resource(setup_resources);
+            render_app.init_resource::<SetupPassRegistry>();
+            render_app
+                .world_mut()
+                .get_resource_or_init::<SetupResources>()
+                .add_node(SwapChainSetupResourceNode);
 
-            render_app.add_systems(Render, init_frame_graph.in_set(FrameGraphSet::ManageViews));
+            render_app.add_systems(
+                Render,
+                (cleanup_frame_graph, init_frame_graph)
+                    .chain()
+                    .in_set(FrameGraphSet::ManageViews),
+            );
             render_app.add_systems(
                 Render,
                 setup_resource_system.in_set(FrameGraphSet::SetupResource),
@@ -97,59 +160,369 @@ impl Plugin for ProtoRenderPlugin {
             render_app.add_systems(Render, setup_pass_system.in_set(FrameGraphSet::SetupPass));
 
             render_app.add_systems(Render, compile_frame_graph.in_set(FrameGraphSet::Compile));
-            render_app.add_systems(Render, execute_frame_graph.in_set(FrameGraphSet::Execute));
+            render_app.add_systems(
+                Render,
+                (
+                    execute_frame_graph,
+                    publish_frame_graph_errors,
+                    collect_present_windows,
+                    take_swap_chain_textures,
+                )
+                    .chain()
+                    .in_set(FrameGraphSet::Execute),
+            );
+            render_app.add_systems(Render, present_frame_graph.in_set(FrameGraphSet::Present));
         }
     }
 }
 
-pub fn compile_frame_graph(mut frame_graphs: Query<&mut FrameGraph>) {
-    for mut frame_graph in frame_graphs.iter_mut() {
-        frame_graph.compile();
+///记录每个实体上一次输出的错误，同样的错误只输出一次
+fn is_new_error(logged: &mut EntityHashMap<String>, entity: Entity, e: &RendererError) -> bool {
+    let message = e.to_string();
+
+    if logged.get(&entity) == Some(&message) {
+        return false;
+    }
+
+    logged.insert(entity, message);
+    true
+}
+
+pub fn compile_frame_graph(
+    mut frame_graphs: Query<(Entity, &mut FrameGraph)>,
+    pipeline_cache: Res<PipelineCache>,
+    mut frame_graph_errors: ResMut<FrameGraphErrors>,
+    mut logged: Local<EntityHashMap<String>>,
+) {
+    frame_graph_errors.errors.clear();
+    logged.retain(|entity, _| frame_graphs.contains(*entity));
+
+    for (entity, mut frame_graph) in frame_graphs.iter_mut() {
+        match frame_graph.compile(&*pipeline_cache) {
+            Ok(()) => {
+                logged.remove(&entity);
+            }
+            Err(e) => {
+                if is_new_error(&mut logged, entity, &e) {
+                    error!("frame graph of {} failed to compile: {}", entity, e);
+                }
+                frame_graph_errors.errors.push((entity, e));
+            }
+        }
+
+        for skipped_pass in frame_graph.skipped_passes() {
+            debug!(
+                "pass `{}` of {} skipped, pipelines not ready: {:?}, skipped dependencies: {:?}",
+                skipped_pass.pass_name,
+                entity,
+                skipped_pass.pending_pipelines,
+                skipped_pass.skipped_dependencies
+            );
+        }
+
+        let report = frame_graph.transient_memory_report();
+        debug!(
+            "frame graph of {} transient memory: peak {} bytes in {} slots, {} bytes without aliasing",
+            entity, report.peak_bytes, report.slot_count, report.unaliased_bytes
+        );
     }
 }
 
 pub fn init_frame_graph(
     mut commands: Commands,
     view_targets: Query<Entity, (Without<FrameGraph>, With<ViewTarget>)>,
+    cameras: Query<&ExtractedCamera>,
+    mut retargeted_views: Query<(
+        Entity,
+        &ExtractedCamera,
+        &mut FrameGraph,
+        &mut FrameGraphTarget,
+    )>,
+    view_passes: Query<&FrameGraphPasses>,
+    mut registry: ResMut<SetupPassRegistry>,
     mut setup_passed: ResMut<SetupPassesFrameGraph>,
 ) {
+    if let Err(e) = registry.sort() {
+        error!("failed to order setup passes: {}", e);
+    }
+
     for view_target in view_targets.iter() {
-        commands.entity(view_target).insert(FrameGraph::default());
-        setup_passed.insert(view_target, SetupPasses::default());
+        let target = cameras
+            .get(view_target)
+            .ok()
+            .and_then(|camera| camera.target.clone());
+
+        commands
+            .entity(view_target)
+            .insert((FrameGraph::default(), FrameGraphTarget(target)));
+        setup_passed.insert(
+            view_target,
+            registry.create_setup_passes(view_passes.get(view_target).ok()),
+        );
+    }
+
+    //相机换了渲染目标时，旧目标的编译缓存和资源都不能再用
+    for (entity, camera, mut frame_graph, mut target) in retargeted_views.iter_mut() {
+        if camera.target == target.0 {
+            continue;
+        }
+
+        *frame_graph = FrameGraph::default();
+        target.0 = camera.target.clone();
+        setup_passed.insert(
+            entity,
+            registry.create_setup_passes(view_passes.get(entity).ok()),
+        );
+    }
+
+    //相机上的 FrameGraphPasses 改变时重新选取节点
+    for (entity, setup_passes) in setup_passed.iter_mut() {
+        let passes = view_passes.get(*entity).ok();
+
+        if setup_passes.pass_names() != passes {
+            *setup_passes = registry.create_setup_passes(passes);
+        }
+    }
+}
+
+///移除已经销毁或者不再是视图的实体上的 FrameGraph 状态
+pub fn cleanup_frame_graph(
+    mut commands: Commands,
+    views: Query<(Entity, Has<ViewTarget>), With<FrameGraph>>,
+    mut setup_passed: ResMut<SetupPassesFrameGraph>,
+) {
+    for (entity, is_view) in views.iter() {
+        if !is_view {
+            commands
+                .entity(entity)
+                .remove::<(FrameGraph, FrameGraphTarget)>();
+        }
     }
+
+    setup_passed.retain(|entity, _| matches!(views.get(*entity), Ok((_, true))));
 }
 
 pub fn execute_frame_graph(
     render_device: Res<ProtoRenderDevice>,
-    mut frame_graphs: Query<&mut FrameGraph>,
+    mut frame_graphs: Query<(Entity, &mut FrameGraph)>,
     mut transient_resource_cache: ResMut<TransientResourceCache>,
     pipeline_cache: Res<PipelineCache>,
-    //mut windows: ResMut<ExtractedWindows>,
+    mut frame_graph_errors: ResMut<FrameGraphErrors>,
+    mut logged: Local<EntityHashMap<String>>,
 ) {
-    for mut frame_graph in frame_graphs.iter_mut() {
-        frame_graph.execute(
+    logged.retain(|entity, _| frame_graphs.contains(*entity));
+
+    for (entity, mut frame_graph) in frame_graphs.iter_mut() {
+        match frame_graph.execute(
             &render_device.device,
             &mut transient_resource_cache,
-            &pipeline_cache,
-        );
+            &*pipeline_cache,
+        ) {
+            Ok(()) => {
+                logged.remove(&entity);
+            }
+            Err(e) => {
+                if is_new_error(&mut logged, entity, &e) {
+                    error!("frame graph of {} failed to execute: {}", entity, e);
+                }
+                frame_graph_errors.errors.push((entity, e));
+            }
+        }
     }
 
-    // for window in windows.values_mut() {
-    //     if let Some(surface_texture) = window.swap_chain_texture.take() {
-    //         // TODO(clean): winit docs recommends calling pre_present_notify before this.
-    //         // though `present()` doesn't present the frame, it schedules it to be presented
-    //         // by wgpu.
-    //         // https://docs.rs/winit/0.29.9/wasm32-unknown-unknown/winit/window/struct.Window.html#method.pre_present_notify
-    //         surface_texture.present();
-    //     }
-    // }
+    transient_resource_cache.advance_frame();
+}
+
+///把渲染世界的错误换成主世界的相机实体后交给主世界
+pub fn publish_frame_graph_errors(
+    frame_graph_errors: Res<FrameGraphErrors>,
+    main_entities: Query<&MainEntity>,
+    shared_errors: Res<SharedFrameGraphErrors>,
+) {
+    let errors = frame_graph_errors
+        .errors
+        .iter()
+        .map(|(entity, e)| {
+            let main_entity = main_entities
+                .get(*entity)
+                .map(MainEntity::id)
+                .unwrap_or(*entity);
+            (main_entity, e.clone())
+        })
+        .collect();
+
+    *shared_errors.0.lock().unwrap() = errors;
+}
+
+///记录本帧 FrameGraph 渲染到的窗口
+pub fn collect_present_windows(
+    targets: Query<&FrameGraphTarget, With<FrameGraph>>,
+    mut present_windows: ResMut<PresentWindows>,
+) {
+    present_windows.windows.clear();
+
+    for target in targets.iter() {
+        if let Some(NormalizedRenderTarget::Window(window_ref)) = target.0.as_ref() {
+            present_windows.windows.insert(window_ref.entity());
+        }
+    }
+}
+
+///在 bevy 呈现之前取走这些窗口的交换链纹理，由 present_frame_graph 呈现
+///bevy 的截图需要读取窗口上的交换链纹理，这些窗口的截图会被跳过
+pub fn take_swap_chain_textures(
+    mut present_windows: ResMut<PresentWindows>,
+    mut extracted_windows: ResMut<ExtractedWindows>,
+) {
+    let PresentWindows {
+        windows,
+        surface_textures,
+    } = &mut *present_windows;
+
+    for window_entity in windows.iter() {
+        if let Some(surface_texture) = extracted_windows
+            .get_mut(window_entity)
+            .and_then(|window| window.swap_chain_texture.take())
+        {
+            surface_textures.push(surface_texture);
+        }
+    }
+}
+
+///render_system 已经执行完渲染图并移除了 ViewTarget，此时交换链的视图都已不再使用
+pub fn present_frame_graph(mut present_windows: ResMut<PresentWindows>) {
+    for surface_texture in present_windows.surface_textures.drain(..) {
+        surface_texture.present();
+    }
+}
+
+pub fn receive_frame_graph_errors(
+    shared_errors: Res<SharedFrameGraphErrors>,
+    mut frame_graph_errors: ResMut<FrameGraphErrors>,
+) {
+    frame_graph_errors.errors = std::mem::take(&mut *shared_errors.0.lock().unwrap());
 }
 
+#[cfg(test)]
 mod test {
+    use bevy::{
+        app::{App, SubApp},
+        ecs::{system::RunSystemOnce, world::World},
+        render::{RenderApp, camera::NormalizedRenderTarget, sync_world::MainEntity},
+        window::WindowRef,
+    };
+
+    use crate::{
+        CameraDriverSetupPassNode, FrameGraph, FrameGraphAppExt, FrameGraphErrors,
+        FrameGraphTarget, PresentWindows, RendererError, SetupPassNode, SetupPassRegistry,
+        SetupPasses, SetupPassesFrameGraph, SharedFrameGraphErrors,
+    };
+
+    use super::{
+        cleanup_frame_graph, collect_present_windows, publish_frame_graph_errors,
+        receive_frame_graph_errors,
+    };
+
     #[test]
     fn test() {
         let a = 5;
 
         assert_eq!(a, 5)
     }
+
+    #[test]
+    fn test_cleanup_frame_graph() {
+        let mut world = World::new();
+        world.init_resource::<SetupPassesFrameGraph>();
+
+        let stale = world
+            .spawn((FrameGraph::default(), FrameGraphTarget(None)))
+            .id();
+        let despawned = world.spawn_empty().id();
+        world.despawn(despawned);
+
+        let mut setup_passes = world.resource_mut::<SetupPassesFrameGraph>();
+        setup_passes.insert(stale, SetupPasses::default());
+        setup_passes.insert(despawned, SetupPasses::default());
+
+        world.run_system_once(cleanup_frame_graph).unwrap();
+
+        assert!(world.resource::<SetupPassesFrameGraph>().is_empty());
+        assert!(world.get::<FrameGraph>(stale).is_none());
+        assert!(world.get::<FrameGraphTarget>(stale).is_none());
+    }
+
+    #[test]
+    fn test_add_frame_graph_pass() {
+        let mut app = App::new();
+        app.insert_sub_app(RenderApp, SubApp::new());
+        app.add_frame_graph_pass::<CameraDriverSetupPassNode>();
+
+        let registry = app
+            .sub_app(RenderApp)
+            .world()
+            .resource::<SetupPassRegistry>();
+        assert!(registry.contains(CameraDriverSetupPassNode::get_pass_name()));
+    }
+
+    #[test]
+    fn test_forward_frame_graph_errors() {
+        let shared_errors = SharedFrameGraphErrors::default();
+
+        let mut main_world = World::new();
+        main_world.init_resource::<FrameGraphErrors>();
+        main_world.insert_resource(shared_errors.clone());
+        let camera = main_world.spawn_empty().id();
+
+        let mut render_world = World::new();
+        render_world.insert_resource(shared_errors);
+        let view = render_world.spawn(MainEntity::from(camera)).id();
+        render_world.insert_resource(FrameGraphErrors {
+            errors: vec![(view, RendererError::backend("lost"))],
+        });
+
+        render_world
+            .run_system_once(publish_frame_graph_errors)
+            .unwrap();
+        main_world
+            .run_system_once(receive_frame_graph_errors)
+            .unwrap();
+
+        let errors = &main_world.resource::<FrameGraphErrors>().errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, camera);
+
+        //同一帧的错误只交给主世界一次
+        main_world
+            .run_system_once(receive_frame_graph_errors)
+            .unwrap();
+        assert!(main_world.resource::<FrameGraphErrors>().errors.is_empty());
+    }
+
+    #[test]
+    fn test_collect_present_windows() {
+        let mut world = World::new();
+        world.init_resource::<PresentWindows>();
+
+        let window = world.spawn_empty().id();
+        let other_window = world.spawn_empty().id();
+        let target = |window| {
+            FrameGraphTarget(Some(NormalizedRenderTarget::Window(
+                WindowRef::Entity(window).normalize(None).unwrap(),
+            )))
+        };
+
+        //两个相机渲染到同一窗口，只需呈现一次
+        world.spawn((FrameGraph::default(), target(window)));
+        world.spawn((FrameGraph::default(), target(window)));
+        world.spawn((FrameGraph::default(), target(other_window)));
+        world.spawn((FrameGraph::default(), FrameGraphTarget(None)));
+
+        world.run_system_once(collect_present_windows).unwrap();
+
+        let windows = world.resource::<PresentWindows>().windows();
+        assert_eq!(windows.len(), 2);
+        assert!(windows.contains(&window));
+        assert!(windows.contains(&other_window));
+    }
 }
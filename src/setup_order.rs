@@ -0,0 +1,155 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use crate::RendererError;
+
+///setup 节点的名称以及声明的依赖
+pub struct SetupNodeDependencies<'a> {
+    pub label: &'a str,
+    ///必须在这些节点之前执行
+    pub before: &'a [&'static str],
+    ///必须在这些节点之后执行
+    pub after: &'a [&'static str],
+}
+
+///保存在注册表中、可以按依赖排序的 setup 节点
+pub trait SetupNodeOrder {
+    fn dependencies(&self) -> SetupNodeDependencies<'_>;
+}
+
+///同名节点替换原来的位置，否则追加到末尾
+pub fn insert_setup_node<T: SetupNodeOrder>(nodes: &mut Vec<T>, node: T) {
+    let label = node.dependencies().label.to_string();
+
+    match nodes
+        .iter_mut()
+        .find(|other| other.dependencies().label == label)
+    {
+        Some(other) => *other = node,
+        None => nodes.push(node),
+    }
+}
+
+///按依赖重新排列节点，失败时保持原来的顺序
+pub fn reorder_setup_nodes<T: SetupNodeOrder>(nodes: &mut Vec<T>) -> Result<(), RendererError> {
+    let dependencies: Vec<SetupNodeDependencies> =
+        nodes.iter().map(|node| node.dependencies()).collect();
+    let order = sort_setup_nodes(&dependencies)?;
+
+    let mut taken: Vec<Option<T>> = std::mem::take(nodes).into_iter().map(Some).collect();
+    *nodes = order
+        .into_iter()
+        .map(|index| taken[index].take().unwrap())
+        .collect();
+
+    Ok(())
+}
+
+///按依赖对 setup 节点拓扑排序，没有依赖关系的节点保持注册顺序
+pub fn sort_setup_nodes(nodes: &[SetupNodeDependencies]) -> Result<Vec<usize>, RendererError> {
+    let index_of = |node: &SetupNodeDependencies, dependency: &str| {
+        nodes
+            .iter()
+            .position(|other| other.label == dependency)
+            .ok_or_else(|| RendererError::MissingSetupDependency {
+                node: node.label.to_string(),
+                dependency: dependency.to_string(),
+            })
+    };
+
+    let mut successors: Vec<Vec<usize>> = vec![vec![]; nodes.len()];
+    let mut in_degrees = vec![0; nodes.len()];
+
+    for (index, node) in nodes.iter().enumerate() {
+        for dependency in node.after.iter() {
+            let from = index_of(node, dependency)?;
+            successors[from].push(index);
+            in_degrees[index] += 1;
+        }
+
+        for dependency in node.before.iter() {
+            let to = index_of(node, dependency)?;
+            successors[index].push(to);
+            in_degrees[to] += 1;
+        }
+    }
+
+    let mut ready: BinaryHeap<Reverse<usize>> = in_degrees
+        .iter()
+        .enumerate()
+        .filter(|(_, in_degree)| **in_degree == 0)
+        .map(|(index, _)| Reverse(index))
+        .collect();
+
+    let mut order = Vec::with_capacity(nodes.len());
+
+    while let Some(Reverse(index)) = ready.pop() {
+        order.push(index);
+
+        for successor in successors[index].iter() {
+            in_degrees[*successor] -= 1;
+
+            if in_degrees[*successor] == 0 {
+                ready.push(Reverse(*successor));
+            }
+        }
+    }
+
+    if order.len() < nodes.len() {
+        let node_names = nodes
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| in_degrees[*index] > 0)
+            .map(|(_, node)| node.label.to_string())
+            .collect();
+
+        return Err(RendererError::SetupNodeCycle { node_names });
+    }
+
+    Ok(order)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::RendererError;
+
+    use super::{SetupNodeDependencies, sort_setup_nodes};
+
+    fn node<'a>(
+        label: &'a str,
+        before: &'a [&'static str],
+        after: &'a [&'static str],
+    ) -> SetupNodeDependencies<'a> {
+        SetupNodeDependencies {
+            label,
+            before,
+            after,
+        }
+    }
+
+    #[test]
+    fn test_sort_setup_nodes() {
+        let nodes = [
+            node("post", &[], &["main"]),
+            node("main", &[], &[]),
+            node("shadow", &["main"], &[]),
+            node("ui", &[], &[]),
+        ];
+
+        assert_eq!(sort_setup_nodes(&nodes).unwrap(), [2, 1, 0, 3]);
+    }
+
+    #[test]
+    fn test_sort_setup_nodes_errors() {
+        let missing = [node("post", &[], &["main"])];
+        assert!(matches!(
+            sort_setup_nodes(&missing),
+            Err(RendererError::MissingSetupDependency { .. })
+        ));
+
+        let cycle = [node("a", &[], &["b"]), node("b", &[], &["a"])];
+        let Err(RendererError::SetupNodeCycle { node_names }) = sort_setup_nodes(&cycle) else {
+            panic!("expected cycle");
+        };
+        assert_eq!(node_names, ["a", "b"]);
+    }
+}
@@ -1,30 +1,48 @@
+use std::sync::Arc;
+
 use bevy::{
     ecs::{
+        component::Component,
         entity::EntityHashMap,
         resource::Resource,
         world::{EntityWorldMut, Mut, World},
     },
-    platform_support::collections::HashMap,
-    prelude::{Deref, DerefMut},
+    log::warn,
+    prelude::{ClearColor, ClearColorConfig, Deref, DerefMut},
+    render::{camera::ExtractedCamera, extract_component::ExtractComponent},
 };
 use downcast_rs::Downcast;
 
 use crate::{
-    ColorAttachment, FrameGraph, PassData, RenderContext, SetupResourceNode, SwapChain,
-    SwapChainSetupResourceNode,
+    ColorAttachment, FrameGraph, LoadOp, PassData, RenderContext, RendererError,
+    ResourceNodeHandle, SetupNodeDependencies, SetupNodeOrder, SetupResourceNode, SwapChain,
+    SwapChainSetupResourceNode, insert_setup_node, reorder_setup_nodes,
 };
 
 pub trait SetupPassNode: Downcast + Send + Sync + 'static {
     fn get_insert_point(&self) -> usize;
     fn get_pass_name() -> &'static str;
 
+    ///setup 排在本节点之后的 SetupPassNode 名称
+    fn get_before() -> &'static [&'static str] {
+        &[]
+    }
+
+    ///setup 排在本节点之前的 SetupPassNode 名称
+    fn get_after() -> &'static [&'static str] {
+        &[]
+    }
+
     fn setup_pass(&self, insert_point: usize, pass_name: &str, world: &mut EntityWorldMut<'_>);
 }
 
+#[derive(Clone)]
 pub struct SetupPassNodeState {
-    node: Box<dyn ErasedSetupPassNode>,
+    node: Arc<dyn ErasedSetupPassNode>,
     label: String,
     insert_point: usize,
+    before: &'static [&'static str],
+    after: &'static [&'static str],
 }
 
 pub trait ErasedSetupPassNode: Downcast + Send + Sync + 'static {
@@ -42,44 +60,134 @@ impl<T: SetupPassNode> ErasedSetupPassNode for T {
     }
 }
 
+impl SetupPassNodeState {
+    pub fn new<T: SetupPassNode>(value: T) -> Self {
+        let insert_point = value.get_insert_point();
+
+        Self {
+            node: Arc::new(value),
+            label: T::get_pass_name().to_string(),
+            insert_point,
+            before: T::get_before(),
+            after: T::get_after(),
+        }
+    }
+}
+
+impl SetupNodeOrder for SetupPassNodeState {
+    fn dependencies(&self) -> SetupNodeDependencies<'_> {
+        SetupNodeDependencies {
+            label: &self.label,
+            before: self.before,
+            after: self.after,
+        }
+    }
+}
+
+///相机上声明此视图使用的 SetupPassNode 名称，会提取到渲染世界
+///
+///没有此组件的视图使用 SetupPassRegistry 中的全部节点
+#[derive(Debug, Default, Clone, PartialEq, Eq, Component, ExtractComponent)]
+pub struct FrameGraphPasses(pub Vec<String>);
+
+impl FrameGraphPasses {
+    pub fn new(pass_names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self(pass_names.into_iter().map(Into::into).collect())
+    }
+}
+
+///所有可用的 SetupPassNode，视图按名称从中选取
+#[derive(Default, Resource)]
+pub struct SetupPassRegistry {
+    ///排序后按依赖顺序排列
+    nodes: Vec<SetupPassNodeState>,
+    sorted: bool,
+}
+
+impl SetupPassRegistry {
+    pub fn add_node<T: SetupPassNode>(&mut self, value: T) {
+        insert_setup_node(&mut self.nodes, SetupPassNodeState::new(value));
+        self.sorted = false;
+    }
+
+    pub fn contains(&self, pass_name: &str) -> bool {
+        self.nodes.iter().any(|node| node.label == pass_name)
+    }
+
+    pub fn pass_names(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().map(|node| node.label.as_str())
+    }
+
+    ///在 create_setup_passes 之前调用，新注册的节点只排序一次
+    pub fn sort(&mut self) -> Result<(), RendererError> {
+        if self.sorted {
+            return Ok(());
+        }
+
+        self.sorted = true;
+        reorder_setup_nodes(&mut self.nodes)
+    }
+
+    ///按 FrameGraphPasses 创建视图的 SetupPasses，没有指定时使用全部节点
+    pub fn create_setup_passes(&self, passes: Option<&FrameGraphPasses>) -> SetupPasses {
+        let mut setup_passes = SetupPasses {
+            pass_names: passes.cloned(),
+            ..Default::default()
+        };
+
+        let Some(passes) = passes else {
+            setup_passes.nodes = self.nodes.clone();
+            return setup_passes;
+        };
+
+        for pass_name in passes.0.iter() {
+            if !self.contains(pass_name) {
+                warn!("setup pass `{}` is not registered", pass_name);
+            }
+        }
+
+        //按注册表中的顺序选取，保证依赖顺序
+        setup_passes.nodes = self
+            .nodes
+            .iter()
+            .filter(|node| passes.0.contains(&node.label))
+            .cloned()
+            .collect();
+
+        setup_passes
+    }
+}
+
 #[derive(Resource)]
 pub struct SetupPasses {
-    nodes: HashMap<String, SetupPassNodeState>,
+    nodes: Vec<SetupPassNodeState>,
     camera_deriver: SetupPassNodeState,
+    ///创建时使用的 FrameGraphPasses，用来判断是否需要重建
+    pass_names: Option<FrameGraphPasses>,
 }
 
 impl Default for SetupPasses {
     fn default() -> Self {
-        let label = CameraDriverSetupPassNode::get_pass_name();
-        let value = CameraDriverSetupPassNode::default();
-        let insert_point = <CameraDriverSetupPassNode as SetupPassNode>::get_insert_point(&value);
-
         Self {
             nodes: Default::default(),
-            camera_deriver: SetupPassNodeState {
-                node: Box::new(value),
-                label: label.to_string(),
-                insert_point,
-            },
+            camera_deriver: SetupPassNodeState::new(CameraDriverSetupPassNode::default()),
+            pass_names: None,
         }
     }
 }
 
 impl SetupPasses {
     pub fn add_node<T: SetupPassNode>(&mut self, value: T) {
-        let label = T::get_pass_name().to_string();
-        let insert_point = value.get_insert_point();
-        let node_state = SetupPassNodeState {
-            node: Box::new(value),
-            label: label.clone(),
-            insert_point,
-        };
+        insert_setup_node(&mut self.nodes, SetupPassNodeState::new(value));
+    }
 
-        self.nodes.insert(label, node_state);
+    pub fn pass_names(&self) -> Option<&FrameGraphPasses> {
+        self.pass_names.as_ref()
     }
 
+    ///相机没有指定 FrameGraphPasses 且没有注册任何节点时，才由 CameraDriverSetupPassNode 清除交换链
     pub fn update(&mut self, world: &mut EntityWorldMut) {
-        if self.nodes.is_empty() {
+        if self.nodes.is_empty() && self.pass_names.is_none() {
             self.camera_deriver.node.setup_pass(
                 self.camera_deriver.insert_point,
                 &self.camera_deriver.label,
@@ -87,7 +195,7 @@ impl SetupPasses {
             );
         }
 
-        for node_state in self.nodes.values_mut() {
+        for node_state in self.nodes.iter() {
             node_state
                 .node
                 .setup_pass(node_state.insert_point, &node_state.label, world);
@@ -101,7 +209,10 @@ pub struct SetupPassesFrameGraph(EntityHashMap<SetupPasses>);
 pub fn setup_pass_system(world: &mut World) {
     world.resource_scope(|world, mut setup_resources: Mut<SetupPassesFrameGraph>| {
         for (entity, setup_passes) in setup_resources.0.iter_mut() {
-            let mut world = world.entity_mut(*entity);
+            //实体可能在 cleanup_frame_graph 之后才被销毁
+            let Ok(mut world) = world.get_entity_mut(*entity) else {
+                continue;
+            };
             setup_passes.update(&mut world);
         }
     });
@@ -122,18 +233,47 @@ impl SetupPassNode for CameraDriverSetupPassNode {
     }
 
     fn setup_pass(&self, insert_point: usize, pass_name: &str, world: &mut EntityWorldMut<'_>) {
-        if let Some(mut frame_graph) = world.get_mut::<FrameGraph>() {
-            let mut builder = frame_graph.create_pass_node_builder(insert_point, pass_name);
+        //按相机的 ClearColorConfig 决定交换链的加载方式
+        let load_op = match world
+            .get::<ExtractedCamera>()
+            .map(|camera| &camera.clear_color)
+        {
+            Some(ClearColorConfig::Custom(color)) => LoadOp::Clear(color.to_linear()),
+            Some(ClearColorConfig::None) => LoadOp::Load,
+            _ => LoadOp::Clear(
+                world
+                    .world()
+                    .get_resource::<ClearColor>()
+                    .map(|clear_color| clear_color.0.to_linear())
+                    .unwrap_or_default(),
+            ),
+        };
 
-            if let Some(swap_chain_handle) = builder
-                .read_from_board::<SwapChain>(SwapChainSetupResourceNode::get_resource_name())
-            {
-                let swap_chain_handle_read = builder.read(swap_chain_handle);
-                builder.add_attachment(ColorAttachment::SwapChain(swap_chain_handle_read));
+        let Some(mut frame_graph) = world.get_mut::<FrameGraph>() else {
+            return;
+        };
 
-                builder.set_pass(CameraDriverPassData {});
-            }
-        }
+        //没有交换链时不创建渲染节点，否则会留下没有 PassData 的节点
+        let Some(swap_chain_handle) = frame_graph
+            .get_resource_board()
+            .get(SwapChainSetupResourceNode::get_resource_name())
+            .map(|raw| {
+                ResourceNodeHandle::<SwapChain>::new(
+                    raw.resource_node_handle(),
+                    raw.resource_handle(),
+                )
+            })
+        else {
+            return;
+        };
+
+        let mut builder = frame_graph.create_pass_node_builder(insert_point, pass_name);
+        let swap_chain_handle_write = builder.write(swap_chain_handle);
+        builder.add_attachment(
+            ColorAttachment::swap_chain(swap_chain_handle_write).with_load_op(load_op),
+        );
+
+        builder.set_pass(CameraDriverPassData {});
     }
 }
 
@@ -144,3 +284,85 @@ impl PassData for CameraDriverPassData {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use bevy::ecs::world::{EntityWorldMut, World};
+
+    use crate::{FrameGraph, gfx_record::fixture::import_swap_chain};
+
+    use super::{FrameGraphPasses, SetupPassNode, SetupPassRegistry};
+
+    struct UiPass;
+
+    impl SetupPassNode for UiPass {
+        fn get_insert_point(&self) -> usize {
+            0
+        }
+
+        fn get_pass_name() -> &'static str {
+            "ui"
+        }
+
+        fn get_after() -> &'static [&'static str] {
+            &["main"]
+        }
+
+        fn setup_pass(&self, _insert_point: usize, _pass_name: &str, _world: &mut EntityWorldMut) {}
+    }
+
+    struct MainPass;
+
+    impl SetupPassNode for MainPass {
+        fn get_insert_point(&self) -> usize {
+            1
+        }
+
+        fn get_pass_name() -> &'static str {
+            "main"
+        }
+
+        fn setup_pass(&self, _insert_point: usize, _pass_name: &str, _world: &mut EntityWorldMut) {}
+    }
+
+    #[test]
+    fn test_create_setup_passes() {
+        let mut registry = SetupPassRegistry::default();
+        registry.add_node(UiPass);
+        registry.add_node(MainPass);
+        registry.sort().unwrap();
+        assert_eq!(registry.pass_names().collect::<Vec<_>>(), ["main", "ui"]);
+
+        let all = registry.create_setup_passes(None);
+        assert_eq!(all.nodes.len(), 2);
+
+        let passes = FrameGraphPasses::new(["ui", "missing"]);
+        let ui_only = registry.create_setup_passes(Some(&passes));
+        assert_eq!(ui_only.nodes.len(), 1);
+        assert_eq!(ui_only.nodes[0].label, "ui");
+        assert_eq!(ui_only.pass_names(), Some(&passes));
+    }
+
+    #[test]
+    fn test_camera_driver_fallback() {
+        let registry = SetupPassRegistry::default();
+        let mut world = World::new();
+
+        //没有指定节点时回退到 camera driver，指定的列表为空或者都没有注册时不回退
+        for (passes, pass_count) in [
+            (None, 1),
+            (Some(FrameGraphPasses(vec![])), 0),
+            (Some(FrameGraphPasses::new(["missing"])), 0),
+        ] {
+            let mut frame_graph = FrameGraph::default();
+            import_swap_chain(&mut frame_graph);
+            let entity = world.spawn(frame_graph).id();
+
+            let mut setup_passes = registry.create_setup_passes(passes.as_ref());
+            setup_passes.update(&mut world.entity_mut(entity));
+
+            let frame_graph = world.get::<FrameGraph>(entity).unwrap();
+            assert_eq!(frame_graph.export().pass_nodes.len(), pass_count);
+        }
+    }
+}
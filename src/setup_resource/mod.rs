@@ -6,7 +6,7 @@ use bevy::{
         system::{Query, Res, SystemState},
         world::{Mut, World},
     },
-    platform_support::collections::HashMap,
+    log::error,
     render::{
         camera::{ExtractedCamera, ManualTextureViews},
         render_asset::RenderAssets,
@@ -16,23 +16,48 @@ use bevy::{
 };
 use downcast_rs::Downcast;
 
-use crate::{FrameGraph, SwapChain, SwapChainInfo, WgpuSwapChain};
+use crate::{
+    FrameGraph, SetupNodeDependencies, SetupNodeOrder, SwapChain, SwapChainInfo, WgpuSwapChain,
+    insert_setup_node, reorder_setup_nodes,
+};
 
 pub trait SetupResourceNode: Downcast + Send + Sync + 'static {
     fn get_resource_name() -> &'static str;
 
+    ///读取了本资源的 SetupResourceNode 名称
+    fn get_before() -> &'static [&'static str] {
+        &[]
+    }
+
+    ///本资源读取的 SetupResourceNode 名称
+    fn get_after() -> &'static [&'static str] {
+        &[]
+    }
+
     fn setup_resource(&self, resource_name: &str, world: &mut World);
 }
 
 pub struct SetupResourceNodeState {
     node: Box<dyn ErasedSetupResourceNode>,
     label: String,
+    before: &'static [&'static str],
+    after: &'static [&'static str],
 }
 
 pub trait ErasedSetupResourceNode: Downcast + Send + Sync + 'static {
     fn setup_resource(&self, resource_name: &str, world: &mut World);
 }
 
+impl SetupNodeOrder for SetupResourceNodeState {
+    fn dependencies(&self) -> SetupNodeDependencies<'_> {
+        SetupNodeDependencies {
+            label: &self.label,
+            before: self.before,
+            after: self.after,
+        }
+    }
+}
+
 impl<T: SetupResourceNode> ErasedSetupResourceNode for T {
     fn setup_resource(&self, resource_name: &str, world: &mut World) {
         <T as SetupResourceNode>::setup_resource(self, resource_name, world);
@@ -41,22 +66,39 @@ impl<T: SetupResourceNode> ErasedSetupResourceNode for T {
 
 #[derive(Resource, Default)]
 pub struct SetupResources {
-    nodes: HashMap<String, SetupResourceNodeState>,
+    ///排序后按依赖顺序排列
+    nodes: Vec<SetupResourceNodeState>,
+    sorted: bool,
 }
 
 impl SetupResources {
     pub fn add_node<T: SetupResourceNode>(&mut self, value: T) {
-        let label = T::get_resource_name().to_string();
         let node_state = SetupResourceNodeState {
             node: Box::new(value),
-            label: label.clone(),
+            label: T::get_resource_name().to_string(),
+            before: T::get_before(),
+            after: T::get_after(),
         };
 
-        self.nodes.insert(label, node_state);
+        insert_setup_node(&mut self.nodes, node_state);
+        self.sorted = false;
+    }
+
+    pub fn resource_names(&self) -> impl Iterator<Item = &str> {
+        self.nodes.iter().map(|node| node.label.as_str())
     }
 
     pub fn update(&mut self, world: &mut World) {
-        for node_state in self.nodes.values_mut() {
+        //新注册的节点只排序一次，失败时按注册顺序执行
+        if !self.sorted {
+            self.sorted = true;
+
+            if let Err(e) = reorder_setup_nodes(&mut self.nodes) {
+                error!("failed to order setup resources: {}", e);
+            }
+        }
+
+        for node_state in self.nodes.iter_mut() {
             node_state.node.setup_resource(&node_state.label, world);
         }
     }
@@ -86,15 +128,13 @@ impl SetupResourceNode for SwapChainSetupResourceNode {
         let (mut frame_graphs, windows, manual_texture_views, images) = state.get_mut(world);
 
         for (camera, mut frame_graph) in frame_graphs.iter_mut() {
-            if camera.target.is_none() {
-                return;
-            }
+            let Some(target) = camera.target.as_ref() else {
+                continue;
+            };
 
-            if let Some(texture_view) = camera.target.as_ref().unwrap().get_texture_view(
-                &windows,
-                &images,
-                &manual_texture_views,
-            ) {
+            if let Some(texture_view) =
+                target.get_texture_view(&windows, &images, &manual_texture_views)
+            {
                 let swap_chain: Arc<SwapChain> = Arc::new(SwapChain::new(WgpuSwapChain {
                     texture_view: texture_view.deref().clone(),
                 }));